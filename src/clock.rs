@@ -0,0 +1,288 @@
+#[allow(clippy::module_inception)]
+pub mod clock {
+    use std::sync::{Arc,Mutex};
+    use std::sync::atomic::{AtomicU64,Ordering};
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context,Poll,Waker};
+    use std::time::{Duration,Instant};
+    use chrono::{DateTime,Local,Utc};
+
+    /// future returned by `Clock::sleep` [时钟等待返回的Future]
+    pub type ClockSleep = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+    /// source of time for parsers and the scheduler [解析器和调度器使用的时间来源]
+    ///
+    /// `now` is the wall clock used for date formats, start and end times,
+    /// `instant` and `sleep` drive the waits between runs.
+    pub trait Clock : Send + Sync {
+        fn now(&self) -> DateTime<Local>;
+        fn now_utc(&self) -> DateTime<Utc> {
+            self.now().with_timezone(&Utc)
+        }
+        // 单调时间，用于计算间隔
+        fn instant(&self) -> Instant;
+        fn sleep(&self,dur:Duration) -> ClockSleep;
+        // 是否随真实时间前进，时间轮只能由这样的时钟驱动
+        fn is_realtime(&self) -> bool { false }
+    }
+
+    /// the system clock, sleeping on tokio timers [系统时钟，使用tokio计时器等待]
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct SystemClock;
+
+    impl Clock for SystemClock {
+        fn now(&self) -> DateTime<Local> {
+            Local::now()
+        }
+
+        fn now_utc(&self) -> DateTime<Utc> {
+            Utc::now()
+        }
+
+        fn instant(&self) -> Instant {
+            Instant::now()
+        }
+
+        fn sleep(&self,dur:Duration) -> ClockSleep {
+            Box::pin(tokio::time::sleep(dur))
+        }
+
+        fn is_realtime(&self) -> bool {
+            true
+        }
+    }
+
+    struct ManualState {
+        now:DateTime<Local>,
+        elapsed:Duration, // 从创建开始前进的时间
+        sleepers:HashMap<u64,(Duration,Option<Waker>)>, // 还没有poll过的等待没有waker
+        woken:usize, // 已唤醒但等待还没有结束
+    }
+
+    struct ManualInner {
+        origin:Instant,
+        state:Mutex<ManualState>,
+        next_id:AtomicU64,
+    }
+
+    /// a clock that only moves when told to [手动推进的时钟，用于确定性的测试]
+    ///
+    /// Waits on the clock end when `advance` or `set` moves past them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::clock::clock::{Clock, ManualClock};
+    /// use grapeTimerR::parsers::parsers;
+    /// use chrono::{Local, TimeZone};
+    /// use std::time;
+    ///
+    /// let clock = ManualClock::new(Local.ymd(2026,3,2).and_hms(4,30,0));
+    /// let next = parsers::parser_next_with("Day 05:00:00",&clock).unwrap();
+    /// assert_eq!(next,Local.ymd(2026,3,2).and_hms(5,0,0));
+    ///
+    /// clock.advance(time::Duration::from_secs(3600));
+    /// let next = parsers::parser_next_with("Day 05:00:00",&clock).unwrap();
+    /// assert_eq!(next,Local.ymd(2026,3,3).and_hms(5,0,0));
+    /// ```
+    #[derive(Clone)]
+    pub struct ManualClock {
+        inner:Arc<ManualInner>,
+    }
+
+    impl ManualClock {
+        pub fn new(start:DateTime<Local>) -> ManualClock {
+            ManualClock {
+                inner:Arc::new(ManualInner {
+                    origin:Instant::now(),
+                    state:Mutex::new(ManualState{ now:start, elapsed:Duration::from_secs(0), sleepers:HashMap::new(), woken:0 }),
+                    next_id:AtomicU64::new(1),
+                }),
+            }
+        }
+
+        /// move the clock forward [时钟前进一段时间]
+        pub fn advance(&self,dur:Duration) {
+            let wakers = match self.inner.state.lock() {
+                Ok(mut state) => {
+                    state.now = state.now + chrono::Duration::from_std(dur).unwrap_or_else(|_| chrono::Duration::zero());
+                    state.elapsed += dur;
+                    let elapsed = state.elapsed;
+                    // 按到期时间和创建顺序唤醒
+                    let mut due:Vec<(Duration,u64)> = state.sleepers.iter().filter(|(_,(at,_))| *at <= elapsed).map(|(k,(at,_))| (*at,*k)).collect();
+                    due.sort();
+                    state.woken += due.len();
+                    due.into_iter().filter_map(|(_,k)| state.sleepers.remove(&k)).filter_map(|(_,w)| w).collect()
+                }
+                Err(_) => { Vec::new() }
+            };
+            // 在锁外唤醒
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+
+        /// set the wall clock, only a later time moves the waits forward [设置当前时间，只有向后调整才会推进等待]
+        pub fn set(&self,at:DateTime<Local>) {
+            let now = self.now();
+            match (at - now).to_std() {
+                Ok(dur) => { self.advance(dur) }
+                Err(_) => {
+                    if let Ok(mut state) = self.inner.state.lock() {
+                        state.now = at;
+                    }
+                }
+            }
+        }
+
+        /// how many waits are pending on the clock [正在等待的数量]
+        pub fn sleepers(&self) -> usize {
+            self.inner.state.lock().map(|v| v.sleepers.len()).unwrap_or(0)
+        }
+
+        /// when the earliest pending wait ends [最早的等待结束的时间]
+        pub fn next_wake(&self) -> Option<DateTime<Local>> {
+            let state = self.inner.state.lock().ok()?;
+            let at = state.sleepers.values().map(|(at,_)| *at).min()?;
+            let left = at.checked_sub(state.elapsed).unwrap_or_default();
+            Some(state.now + chrono::Duration::from_std(left).unwrap_or_else(|_| chrono::Duration::zero()))
+        }
+
+        // 把时钟推进到最早的等待，只唤醒这一个，超过until时返回false
+        #[cfg(any(test, feature = "testing"))]
+        pub(crate) fn step(&self,until:DateTime<Local>) -> bool {
+            let waker = match self.inner.state.lock() {
+                Ok(mut state) => {
+                    let first = state.sleepers.iter().map(|(k,(at,_))| (*at,*k)).min();
+                    let (at,id) = match first {
+                        Some(v) => { v }
+                        None => { return false; }
+                    };
+                    let left = at.checked_sub(state.elapsed).unwrap_or_default();
+                    let wake_at = state.now + chrono::Duration::from_std(left).unwrap_or_else(|_| chrono::Duration::zero());
+                    if wake_at > until {
+                        return false;
+                    }
+                    state.now = wake_at;
+                    state.elapsed += left;
+                    state.woken += 1;
+                    state.sleepers.remove(&id).map(|(_,w)| w)
+                }
+                Err(_) => { None }
+            };
+            match waker {
+                Some(w) => {
+                    if let Some(w) = w {
+                        w.wake();
+                    }
+                    true
+                }
+                None => { false }
+            }
+        }
+
+        // 已唤醒但对应的等待还没有结束的数量
+        #[cfg(any(test, feature = "testing"))]
+        pub(crate) fn woken(&self) -> usize {
+            self.inner.state.lock().map(|v| v.woken).unwrap_or(0)
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> DateTime<Local> {
+            match self.inner.state.lock() {
+                Ok(v) => { v.now }
+                Err(e) => { e.into_inner().now }
+            }
+        }
+
+        fn instant(&self) -> Instant {
+            let elapsed = match self.inner.state.lock() {
+                Ok(v) => { v.elapsed }
+                Err(e) => { e.into_inner().elapsed }
+            };
+            self.inner.origin + elapsed
+        }
+
+        fn sleep(&self,dur:Duration) -> ClockSleep {
+            let id = self.inner.next_id.fetch_add(1,Ordering::Relaxed);
+            let mut state = match self.inner.state.lock() {
+                Ok(v) => { v }
+                Err(e) => { e.into_inner() }
+            };
+            let deadline = state.elapsed + dur;
+            // 创建时就登记，避免在第一次poll之前被advance跳过
+            let registered = dur > Duration::from_secs(0);
+            if registered {
+                state.sleepers.insert(id,(deadline,None));
+            }
+            Box::pin(ManualSleep {
+                inner:self.inner.clone(),
+                id,
+                deadline,
+                registered,
+            })
+        }
+    }
+
+    struct ManualSleep {
+        inner:Arc<ManualInner>,
+        id:u64,
+        deadline:Duration,
+        registered:bool,
+    }
+
+    impl ManualSleep {
+        // 已经登记的等待离开时钟，被advance移除的计入woken
+        fn release(&mut self,state:&mut ManualState) {
+            if !self.registered {
+                return;
+            }
+            self.registered = false;
+            if state.sleepers.remove(&self.id).is_none() {
+                state.woken = state.woken.saturating_sub(1);
+            }
+        }
+    }
+
+    impl Future for ManualSleep {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let this = self.get_mut();
+            let inner = this.inner.clone();
+            let mut state = match inner.state.lock() {
+                Ok(v) => { v }
+                Err(_) => { return Poll::Ready(()); }
+            };
+            if this.registered && state.sleepers.contains_key(&this.id) {
+                // 已经到期也要等时钟按顺序唤醒
+                state.sleepers.insert(this.id,(this.deadline,Some(cx.waker().clone())));
+                return Poll::Pending;
+            }
+            // 到期后在丢弃时才离开时钟
+            if state.elapsed >= this.deadline {
+                return Poll::Ready(());
+            }
+            if this.registered {
+                // 被唤醒但还没到期，重新登记
+                state.woken = state.woken.saturating_sub(1);
+            }
+            this.registered = true;
+            state.sleepers.insert(this.id,(this.deadline,Some(cx.waker().clone())));
+            Poll::Pending
+        }
+    }
+
+    impl Drop for ManualSleep {
+        fn drop(&mut self) {
+            let inner = self.inner.clone();
+            let lock = inner.state.lock();
+            if let Ok(mut state) = lock {
+                self.release(&mut state);
+            }
+        }
+    }
+}
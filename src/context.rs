@@ -0,0 +1,163 @@
+#[allow(clippy::module_inception)]
+pub mod context {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool,Ordering};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::time::Duration;
+    use tokio::sync::Notify;
+    use chrono::{DateTime,Local};
+    use crate::schedule::schedule::{Schedule, RunLimit};
+    use crate::task::task::{TaskControl, TaskHandle, TaskStatus};
+    use crate::errors::errors::TResult;
+
+    // 单次执行的取消标记
+    struct RunFlag {
+        cancelled:AtomicBool,
+        notify:Notify,
+    }
+
+    /// cooperative cancellation of one execution [单次执行的取消标记，用于任务体主动退出]
+    ///
+    /// Cancelled when the task is stopped or ended, or when the execution times out.
+    /// A sync body can not be interrupted, it should check `is_cancelled` and return early.
+    #[derive(Clone)]
+    pub struct CancelToken {
+        run:Arc<RunFlag>,
+        control:Arc<TaskControl>,
+    }
+
+    impl CancelToken {
+        fn new(control:Arc<TaskControl>) -> CancelToken {
+            CancelToken {
+                run:Arc::new(RunFlag{ cancelled:AtomicBool::new(false), notify:Notify::new() }),
+                control,
+            }
+        }
+
+        /// whether the execution should stop [是否需要停止执行]
+        pub fn is_cancelled(&self) -> bool {
+            self.run.cancelled.load(Ordering::SeqCst) || self.control.status().is_terminal()
+        }
+
+        /// wait until cancelled [等待被取消]
+        pub async fn cancelled(&self) {
+            let notified = self.run.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            tokio::select! {
+                _ = notified => {}
+                _ = self.control.wait() => {}
+            }
+        }
+
+        pub(crate) fn cancel(&self) {
+            self.run.cancelled.store(true,Ordering::SeqCst);
+            self.run.notify.notify_waiters();
+        }
+    }
+
+    /// context of one execution [单次执行的上下文]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use grapeTimerR::schedule::schedule::{Schedule, TaskOptions};
+    /// use std::time;
+    ///
+    /// timer::spawn_ctx(Schedule::Tick(time::Duration::from_millis(10)),0,TaskOptions::default(),|ctx| -> Result<(),String> {
+    ///         println!("id:{} run:{} late ms:{} left:{:?}",ctx.id,ctx.run_index,ctx.lateness.as_millis(),ctx.runs_left);
+    ///         if ctx.run_index >= 3 {
+    ///             ctx.stop();
+    ///         }
+    ///         Ok(())
+    ///     }).unwrap();
+    /// ```
+    #[derive(Clone)]
+    pub struct ExecContext {
+        pub id:u64,
+        pub run_index:u64, // 第几次执行，从1开始
+        pub scheduled_at:DateTime<Local>, // 计划的执行时间
+        pub started_at:DateTime<Local>, // 实际开始的时间
+        pub lateness:Duration, // 实际开始比计划晚的时间
+        pub runs_left:Option<u64>, // 这次执行之后剩余的次数，不限次数时为None
+        token:CancelToken,
+        handle:TaskHandle,
+    }
+
+    impl ExecContext {
+        pub(crate) fn new(handle:TaskHandle,run_index:u64,scheduled_at:DateTime<Local>) -> ExecContext {
+            let control = handle.control().clone();
+            let started_at = control.clock().now();
+            ExecContext {
+                id:handle.id(),
+                run_index,
+                scheduled_at,
+                started_at,
+                lateness:(started_at - scheduled_at).to_std().unwrap_or_default(),
+                runs_left:control.runs_left(),
+                token:CancelToken::new(control),
+                handle,
+            }
+        }
+
+        // 不在调度器中执行时使用，控制操作不会生效
+        pub(crate) fn detached(id:u64) -> ExecContext {
+            let control = Arc::new(TaskControl::new(id,Schedule::Tick(Duration::from_secs(0)),RunLimit::Forever,Vec::new()));
+            let handle = TaskHandle::new(control,Arc::new(std::sync::Mutex::new(HashMap::new())));
+            ExecContext::new(handle,0,Local::now())
+        }
+
+        /// cancellation token of this execution [这次执行的取消标记]
+        pub fn token(&self) -> &CancelToken {
+            &self.token
+        }
+
+        /// whether the execution should stop [是否需要停止执行]
+        pub fn is_cancelled(&self) -> bool {
+            self.token.is_cancelled()
+        }
+
+        /// wait on the clock of the scheduler, a `TestScheduler` moves it forward [在调度器的时钟上等待，测试调度器推进这个时钟]
+        pub async fn sleep(&self,dur:Duration) {
+            self.handle.control().timer().sleep(dur).await
+        }
+
+        /// handle of the task [任务句柄]
+        pub fn handle(&self) -> &TaskHandle {
+            &self.handle
+        }
+
+        /// stop the task after this execution [在这次执行后停止任务]
+        pub fn stop(&self) {
+            self.handle.cancel();
+        }
+
+        /// change the schedule of the task [修改任务的执行计划]
+        pub fn reschedule(&self,schedule:Schedule) -> TResult<()> {
+            self.handle.reschedule(schedule)
+        }
+
+        /// status of the task [任务状态]
+        pub fn status(&self) -> TaskStatus {
+            self.handle.status()
+        }
+    }
+
+    impl fmt::Debug for ExecContext {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_struct("ExecContext")
+                .field("id",&self.id)
+                .field("run_index",&self.run_index)
+                .field("scheduled_at",&self.scheduled_at)
+                .field("started_at",&self.started_at)
+                .field("lateness",&self.lateness)
+                .field("runs_left",&self.runs_left)
+                .finish()
+        }
+    }
+}
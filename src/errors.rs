@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod errors {
     use std::error::Error;
     use std::fmt;
@@ -9,6 +10,10 @@ pub mod errors {
         WeekDay,
         AllocTickerError,
         MutexError,
+        TaskNotFound,
+        TaskExists,
+        Cancelled,
+        HandlerNotFound,
         Other(String),
     }
 
@@ -27,6 +32,10 @@ pub mod errors {
                 TErrorKind::WeekDay => { String::from("error,bad week day...") }
                 TErrorKind::AllocTickerError  => { String::from("bad alloc ticker...") }
                     TErrorKind::MutexError => { String::from("get mutex lock error...") }
+                    TErrorKind::TaskNotFound => { String::from("error,task not found...") }
+                    TErrorKind::TaskExists => { String::from("error,task id already exists...") }
+                    TErrorKind::Cancelled => { String::from("error,task cancelled...") }
+                    TErrorKind::HandlerNotFound => { String::from("error,job handler not registered...") }
                 TErrorKind::Other(v) => { v.clone() }
             } }
         }
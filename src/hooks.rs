@@ -0,0 +1,187 @@
+#[allow(clippy::module_inception)]
+pub mod hooks {
+    // 测试调度器观察任务活动和执行的记录，只在测试和testing特性下编译
+    // 其他情况下守卫和登记都是空操作，调度器的路径上没有额外的开销
+    #[cfg(any(test, feature = "testing"))]
+    pub use self::observed::*;
+    #[cfg(not(any(test, feature = "testing")))]
+    pub(crate) use self::unobserved::*;
+
+    #[cfg(any(test, feature = "testing"))]
+    mod observed {
+        use std::sync::{Arc,Mutex};
+        use chrono::{DateTime,Local};
+        use crate::wheel::wheel::Timer;
+
+        /// one execution recorded by `TestScheduler` [测试调度器记录的一次执行]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct ExecRecord {
+            pub id:u64,
+            pub run_index:u64, // 第几次执行，从1开始
+            pub scheduled_at:DateTime<Local>,
+            pub started_at:DateTime<Local>, // 按测试时钟开始执行的时间
+            pub result:Result<(),String>, // 重试之后的最终结果
+        }
+
+        // 任务循环和执行的观察者，测试调度器用它判断任务是否都在等待并记录执行
+        // 活动的数量包括任务循环、执行，以及被通知唤醒但还没有继续执行的等待
+        pub(crate) trait RunHooks : Send + Sync {
+            fn enter(&self,n:usize);
+            fn leave(&self,n:usize);
+            fn record(&self,record:ExecRecord);
+        }
+
+        pub(crate) type Hooks = Arc<dyn RunHooks>;
+
+        // 存在期间计为活动
+        pub(crate) struct Busy(Option<Hooks>);
+
+        impl Busy {
+            pub fn new(timer:&Timer) -> Busy {
+                if let Some(h) = timer.hooks() {
+                    h.enter(1);
+                }
+                Busy(timer.hooks().cloned())
+            }
+        }
+
+        impl Drop for Busy {
+            fn drop(&mut self) {
+                if let Some(h) = &self.0 {
+                    h.leave(1);
+                }
+            }
+        }
+
+        // 存在期间计为等待
+        pub(crate) struct Idle(Option<Hooks>);
+
+        impl Idle {
+            pub fn new(timer:&Timer) -> Idle {
+                if let Some(h) = timer.hooks() {
+                    h.leave(1);
+                }
+                Idle(timer.hooks().cloned())
+            }
+        }
+
+        impl Drop for Idle {
+            fn drop(&mut self) {
+                if let Some(h) = &self.0 {
+                    h.enter(1);
+                }
+            }
+        }
+
+        // 等待通知的数量，通知时一次取走，被取走的等待在继续执行后才离开
+        #[derive(Default)]
+        struct WaitState {
+            generation:u64,
+            waiting:usize,
+        }
+
+        // 任务上等待通知的登记，只在安装了观察者时使用
+        #[derive(Default)]
+        pub(crate) struct Waiters {
+            state:Mutex<WaitState>,
+        }
+
+        impl Waiters {
+            pub fn new() -> Waiters {
+                Waiters::default()
+            }
+
+            // 唤醒之前调用，被唤醒的等待计为活动
+            pub fn wake(&self,timer:&Timer) {
+                let hooks = match timer.hooks() {
+                    Some(v) => { v }
+                    None => { return; }
+                };
+                let woken = match self.state.lock() {
+                    Ok(mut state) => {
+                        state.generation += 1;
+                        std::mem::take(&mut state.waiting)
+                    }
+                    Err(_) => { 0 }
+                };
+                if woken > 0 {
+                    hooks.enter(woken);
+                }
+            }
+
+            // 在检查状态之前登记，之后的唤醒都会被计入
+            pub fn register<'a>(&'a self,timer:&Timer) -> Waiting<'a> {
+                let hooks = match timer.hooks() {
+                    Some(v) => { v }
+                    None => { return Waiting{ waiters:self, hooks:None, generation:0 }; }
+                };
+                let generation = match self.state.lock() {
+                    Ok(mut state) => {
+                        state.waiting += 1;
+                        state.generation
+                    }
+                    Err(e) => { e.into_inner().generation }
+                };
+                Waiting{ waiters:self, hooks:Some(hooks.clone()), generation }
+            }
+        }
+
+        // 一次登记，离开时没有被唤醒就取消登记，被唤醒的结束活动
+        pub(crate) struct Waiting<'a> {
+            waiters:&'a Waiters,
+            hooks:Option<Hooks>,
+            generation:u64,
+        }
+
+        impl Drop for Waiting<'_> {
+            fn drop(&mut self) {
+                let hooks = match &self.hooks {
+                    Some(v) => { v }
+                    None => { return; }
+                };
+                let woken = match self.waiters.state.lock() {
+                    Ok(mut state) => {
+                        if state.generation == self.generation {
+                            state.waiting -= 1;
+                            false
+                        }else {
+                            true
+                        }
+                    }
+                    Err(_) => { false }
+                };
+                if woken {
+                    hooks.leave(1);
+                }
+            }
+        }
+    }
+
+    #[cfg(not(any(test, feature = "testing")))]
+    mod unobserved {
+        use crate::wheel::wheel::Timer;
+
+        pub(crate) struct Busy;
+
+        impl Busy {
+            pub fn new(_:&Timer) -> Busy { Busy }
+        }
+
+        pub(crate) struct Idle;
+
+        impl Idle {
+            pub fn new(_:&Timer) -> Idle { Idle }
+        }
+
+        #[derive(Default)]
+        pub(crate) struct Waiters;
+
+        impl Waiters {
+            pub fn new() -> Waiters { Waiters }
+            pub fn wake(&self,_:&Timer) {}
+            pub fn register(&self,_:&Timer) -> Waiting { Waiting }
+        }
+
+        pub(crate) struct Waiting;
+    }
+}
@@ -21,6 +21,29 @@ grapeTimer的Rust版本，提供相同功能以及相同类型的服务。
 - 多线程调度模式(Multi-thread scheduling mode)
 - 时间周期，次数多模式可控`[支持每天、每周、每月]`(Time period, multi-mode controllable number of times `[support daily, weekly, monthly]`)
 - 可以获取下一次执行时间`[Chrono Datetime]`(Can get the string of the next execution time)
+- 多个独立调度器实例，各自拥有运行时、配置和ID序列(Multiple independent Scheduler instances, each with its own runtime, config and Id sequence)
+- 每个任务返回TaskHandle，可停止、暂停、恢复、查询状态并等待结束(Every spawn returns a TaskHandle to cancel, pause, resume, query and await the task)
+- 运行中修改任务的间隔或日期格式，保留ID和执行次数(Reschedule a running task's interval or date format, keeping its id and run count)
+- 查询所有任务的执行计划、次数、上次和下次执行时间及状态(List and inspect tasks: schedule, runs, last and next run time, state)
+- 任务分组标签，按组停止、暂停和查询(Task tags, stop, pause and list a group of tasks)
+- 异步任务体，支持AsyncTaskAction和async闭包(Async task bodies with AsyncTaskAction and async closures)
+- 隔离任务中的panic并记录失败，可配置失败策略(Panics in task bodies are isolated and recorded, with a per task failure policy)
+- 可失败的任务，支持重试次数、退避策略和随机抖动(Fallible tasks with retry attempts, backoff and jitter)
+- 单次执行超时，超时的执行被取消并记为失败(Per run timeout, an overrunning run is cancelled and recorded as failure)
+- 执行时间超过间隔时可串行、跳过、排队或并行执行(Overlap policy when an execution outlasts its interval: serial, skip, queue or concurrent)
+- 固定频率的间隔任务，不累积误差，落后时可补齐、延后或跳过(Fixed rate tickers without drift, missed ticks can burst, delay or skip)
+- 按时钟边界对齐的间隔任务，如每5分钟的整点(Tickers aligned to wall-clock boundaries, like every 5 minutes on the minute)
+- 随机抖动和按key固定分散，避免相同计划的任务同时执行(Jitter and deterministic spread by key, so tasks with the same schedule do not fire at once)
+- 开始时间、初始延迟和结束时间，如活动期间每10分钟执行(Start time, initial delay and end time bounds, like every 10 minutes during an event)
+- 单次任务，在指定时间(任意时区)或延迟后执行，可取消或等待结果(One-shot tasks at a time in any time zone or after a delay, cancel or await the result)
+- 执行次数或截止时间RunLimit，最后一次执行后立即结束，可查询剩余次数(RunLimit by times or end time, the task ends right after its last run, runs left can be queried)
+- 执行上下文ExecContext，包含执行序号、计划和实际时间、延迟、剩余次数和取消标记，可在任务体内停止或修改计划(ExecContext with run index, scheduled and actual start, lateness, runs left and a cancellation token, stop or reschedule from inside the body)
+- 可变状态的闭包任务，计数器或缓冲区由任务自己持有，不需要Mutex(Stateful FnMut closures, the task owns its counters and buffers without a Mutex)
+- 同步任务体可在工作线程、阻塞线程池或独占线程中执行，耗时任务不影响其他计时器(Sync task bodies run inline, on a sized blocking pool or on a dedicated thread, so heavy jobs do not starve other timers)
+- 可选的分层时间轮计时器，O(1)插入和取消，几十万个计时器由少量线程驱动(Optional hashed hierarchical timing wheel, O(1) insert and cancel, hundreds of thousands of timers driven by a few threads)
+- 可替换的时钟Clock，解析器和调度器从同一个时钟读取时间和等待，ManualClock用于确定性的测试(Injectable Clock for parsers and the scheduler, ManualClock makes date schedules testable deterministically)
+- 测试调度器TestScheduler，推进手动时钟时依次同步执行到期的任务并记录执行日志，需要testing特性(TestScheduler advances a manual clock, running due tasks in order and recording an execution log, behind the testing feature)
+- 可持久化的任务JobStore，保存在内存或JSON文件中，init_schedule时按名字重新绑定处理函数(JobStore persists jobs in memory or a JSON file, init_schedule rebinds them to handlers registered by name)
 - 自定义起始TimerId的种子(Customize the seed of the starting TimerId)
 - 自定义TimerId的生成函数`[自生成ID请注意并发场景下的线程争抢]`(Custom TimerId generation trait `[Self-generated ID, please pay attention to thread contention in concurrent scenarios]`)
 - TimerId扩展为i64，支持大ID和timestampId生成器(TimerId is i64, supporting large Id and timestampId generator correspondence)
@@ -36,69 +59,81 @@ The scheduler has a light date pattern analysis system, which can provide daily,
 |Day|Day 00:00:00|生成每日的日期时间|
 |Week|Week 1 00:00:00|生成每周的日期时间， 0~6 分别代表周日到周六 |
 |Month|Month 1 00:00:00|生成每月该日期的时间，建议不要使用28日之后的日期|
+|Every|Every 5m +30s Epoch|按时钟边界对齐的间隔，单位ms/s/m/h/d，可选偏移，默认从所在时区的零点开始，Local/Utc/Midnight+08:00指定零点的时区，Epoch从unix纪元开始|
 
 |Key|Format |Description|
 |:----------:|:-------:|:----------:|
 |Day|Day 00:00:00|Generate daily date and time|
 |Week|Week 1 00:00:00|Generate weekly date and time, 0~6 represent Sunday to Saturday|
 |Month|Month 1 00:00:00|The time when the date of the month was generated, it is recommended not to use the date after the 28th|
+|Every|Every 5m +30s Epoch|Interval aligned to wall-clock boundaries, unit ms/s/m/h/d, optional offset, counted from midnight by default, from midnight in a chosen zone with Local/Utc/Midnight+08:00, or from the unix epoch with Epoch|
 */
 
+// crate名沿用grapeTimerR，只能在crate上放开命名检查，各个模块重新打开
+#![allow(non_snake_case)]
+
+#[warn(non_snake_case)]
 pub mod schedule;
+#[warn(non_snake_case)]
 pub mod parsers;
+#[warn(non_snake_case)]
 pub mod errors;
+#[warn(non_snake_case)]
+pub mod scheduler;
+#[warn(non_snake_case)]
+pub mod task;
+#[warn(non_snake_case)]
+pub mod context;
+#[warn(non_snake_case)]
+pub mod clock;
+#[cfg(any(test, feature = "testing"))]
+#[warn(non_snake_case)]
+pub mod testing;
+#[warn(non_snake_case)]
+pub mod store;
+#[warn(non_snake_case)]
 mod thread;
+#[warn(non_snake_case)]
 mod uuid;
+#[warn(non_snake_case)]
+mod wheel;
+#[warn(non_snake_case)]
+mod hooks;
 
 pub use crate::uuid::uuid::IDMode;
+pub use crate::scheduler::scheduler::Scheduler;
+pub use crate::task::task::{TaskHandle, TaskInfo, TaskStatus, OnceHandle};
+pub use crate::context::context::{ExecContext, CancelToken};
+pub use crate::clock::clock::{Clock, SystemClock, ManualClock};
+#[cfg(any(test, feature = "testing"))]
+pub use crate::testing::testing::{TestScheduler, ExecRecord};
+pub use crate::store::store::{JobStore, JobRecord, MemoryJobStore, FileJobStore};
 
+#[warn(non_snake_case)]
 pub mod timer {
-    use std::sync::{Mutex,Arc};
+    use std::sync::Arc;
     use std::time;
-    use crate::schedule::schedule;
-    use crate::schedule::schedule::{TaskAction, ClosuresAction};
+    use crate::schedule::schedule::{TaskAction, Schedule, TaskOptions, AsyncTaskAction, RunLimit};
     use crate::errors::errors::{TResult, TError, TErrorKind};
-    use crate::IDMode;
+    use crate::scheduler::scheduler::Scheduler;
+    use crate::task::task::{TaskHandle, TaskInfo, OnceHandle};
+    use crate::context::context::ExecContext;
+    use chrono::{DateTime, TimeZone};
     use lazy_static::*;
-    use crate::thread::threads::{TaskPool};
-    use crate::uuid::uuid::{set_seed, next_timestamp_id, next_big_id};
-    use simple_log;
     use simple_log::LogConfigBuilder;
     use std::future::Future;
+    use std::fmt;
 
-    #[derive(Clone)]
-    struct InterData {
-        config:Arc<Mutex<Config>>,
-        thread_pool:Arc<Mutex<TaskPool>>,
-    }
+    pub use crate::scheduler::scheduler::{Config, TimerBackend};
 
-    #[derive(Clone)]
-    pub struct Config {
-        pub debug:bool,
-        pub debug_log:String,
-        pub thread_count:i32,
-        pub id_seed:i64, // 起始ID
-        pub id_type:IDMode,
+    lazy_static! {
+        // 默认的调度器实例，timer下的函数都通过它执行
+        static ref DEFAULT:Scheduler = Scheduler::new(Config::default());
     }
 
-    lazy_static! {
-        static ref Inter:InterData = InterData{
-           config:Arc::new(Mutex::new(Config{
-                debug:false,
-                debug_log:String::new(),
-                thread_count:4,
-                id_seed:1, // 起始ID
-                id_type:IDMode::SequenceId,
-           })),
-            thread_pool:Arc::new(Mutex::new(TaskPool::new(time::Duration::from_secs(1),4)))
-         };
-    }
-
-    fn next_uuid() -> u64 {
-        match Inter.config.lock().unwrap().id_type {
-            IDMode::SequenceId => { next_big_id() as u64 }
-            IDMode::TimestampId => { next_timestamp_id() as u64 }
-        }
+    /// get the default scheduler instance [获取默认的调度器实例]
+    pub fn default_scheduler() -> Scheduler {
+        DEFAULT.clone()
     }
 
     /// init schedule system [用于初始化调度系统，通过Config]
@@ -106,47 +141,51 @@ pub mod timer {
     /// # Examples
     ///
     /// ```
-    /// use grapeTimerR::{timer::Config,IDMode, timer};
+    /// use grapeTimerR::{timer::{Config, TimerBackend},IDMode, timer, SystemClock};
+    /// use std::sync::Arc;
     ///
     /// let conf = Config{
     ///         // output log info
     ///         debug: false,
     ///         debug_log:String::from("logs/grapeTimer.log"),
     ///         thread_count: 10,
+    ///         // ExecMode::Blocking的任务使用的阻塞线程数
+    ///         // Size of the blocking pool used by ExecMode::Blocking tasks
+    ///         blocking_thread_count: 16,
     ///         // 初始化全局ID的起始ID，可以自行控制
     ///         // Initialize the starting ID of the global ID, which can be controlled by yourself
     ///         id_seed: 1,
-    ///         id_type: IDMode::SequenceId
+    ///         id_type: IDMode::SequenceId,
+    ///         // 单次执行的默认超时，超时的执行被中止并记为失败
+    ///         // Default timeout of one run, an overrunning run is cancelled and recorded as failure
+    ///         default_timeout: Some(std::time::Duration::from_secs(60)),
+    ///         // 大量计时器时可以使用TimerBackend::Wheel，由少量线程按固定粒度驱动
+    ///         // With very large timer counts use TimerBackend::Wheel, driven by a few threads at a fixed resolution
+    ///         timer_backend: TimerBackend::Tokio,
+    ///         // 读取时间和等待使用的时钟，测试时可以使用ManualClock
+    ///         // Clock for time reads and sleeps, use ManualClock in tests
+    ///         clock: Arc::new(SystemClock),
+    ///         // 保存spawn_job创建的任务，init_schedule时恢复，如FileJobStore
+    ///         // Where spawn_job tasks are kept and restored from on init_schedule, e.g. FileJobStore
+    ///         job_store: None,
     ///     };
     ///
     /// timer::init_schedule(conf);
     /// ```
     pub fn init_schedule(conf:Config) -> TResult<()> {
-        let mut l_config = Inter.config.lock().unwrap();
-        l_config.thread_count = conf.thread_count;
-        l_config.id_seed = conf.id_seed;
-        l_config.debug = conf.debug;
-        l_config.id_type = conf.id_type;
-
         let config = LogConfigBuilder::builder()
-            .path(conf.debug_log)
-            .size(1 * 100)
+            .path(conf.debug_log.clone())
+            .size(100)
             .roll_count(10)
             .level("debug")
             .output_file()
             .output_console()
             .build();
-        let r = simple_log::new(config);
-        match r {
-            Err(e) => {
-                return Err(TError::new(TErrorKind::Other(e)));
-            }
-            Ok(v) => {}
+        if let Err(e) = simple_log::new(config) {
+            return Err(TError::new(TErrorKind::Other(e)));
         }
 
-        Inter.thread_pool.lock().unwrap().rebuild(conf.thread_count,conf.debug);
-        set_seed(l_config.id_seed);
-        Ok(())
+        DEFAULT.rebuild(conf)
     }
 
     /// create a new ticker action [创建一个计时器任务]
@@ -157,27 +196,205 @@ pub mod timer {
     /// use grapeTimerR::timer;
     /// use std::time;
     ///
-    /// fn executor_task(id:u64) {
+    /// fn executor_task(_id:u64) {
     ///     println!("on function mode:{}",chrono::Local::now().to_rfc2822());
     /// }
     /// // 使用函数方式执行代码 Use function to execute code
     ///  timer::spawn_ticker(time::Duration::from_millis(5000),2,executor_task);
     ///  // 使用闭包模式 Use closure function
-    ///  timer::spawn_ticker(time::Duration::from_millis(5000),2,|x| {
+    ///  timer::spawn_ticker(time::Duration::from_millis(5000),2,|_x| {
     ///         println!("on ticker:{}",chrono::Local::now().to_rfc2822());
     ///     });
     /// ```
-    pub fn spawn_ticker(tick:time::Duration, loopCount:i32, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<u64> {
-        let task_action =  ClosuresAction::new("", next_uuid(), loopCount, tick, f);
-        let r = Inter.thread_pool.lock();
-        match r {
-            Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) },
-            Ok(mut v) => {
-                let task_id = task_action.id();
-                v.spawn(Arc::new(task_action));
-                Ok(task_id)
-            }
-        }
+    pub fn spawn_ticker(tick:time::Duration, limit:impl Into<RunLimit>, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_ticker(tick,limit,f)
+    }
+
+    /// create a new ticker action with a stateful closure [创建一个使用可变闭包的计时器任务]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use std::time;
+    ///
+    /// // 状态由任务持有，不需要Mutex或原子变量 the task owns the state, no Mutex or atomics needed
+    /// let mut samples = Vec::new();
+    /// timer::spawn_ticker_mut(time::Duration::from_millis(5000),0,move |id| {
+    ///         samples.push(chrono::Local::now());
+    ///         println!("on ticker:{} samples:{}",id,samples.len());
+    ///     }).unwrap();
+    /// ```
+    pub fn spawn_ticker_mut(tick:time::Duration, limit:impl Into<RunLimit>, f: impl FnMut(u64) + Send + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_ticker_mut(tick,limit,f)
+    }
+
+    /// run a closure once after the delay [延迟一段时间后执行一次]
+    ///
+    /// the handle can be cancelled, awaiting it returns the closure's result, a zero delay runs immediately
+    /// [返回的句柄可以取消，等待时返回闭包的结果，延迟为0时立即执行]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use std::time;
+    ///
+    /// let once = timer::spawn_after(time::Duration::from_millis(10),|id| {
+    ///         format!("delayed:{}",id)
+    ///     }).unwrap();
+    /// let value = timer::block_on_rt(async move { once.await }).unwrap().unwrap();
+    /// println!("{}",value);
+    /// ```
+    pub fn spawn_after<T,F>(delay:time::Duration, f:F) -> TResult<OnceHandle<T>>
+        where
+            F: FnOnce(u64) -> T + Send + 'static,
+            T: Send + 'static,
+    {
+        DEFAULT.spawn_after(delay,f)
+    }
+
+    /// run a closure once at the time, in any time zone [在指定时间执行一次，支持任意时区]
+    ///
+    /// the wait is checked against the clock again before running, a time in the past runs immediately
+    /// [执行前按时钟重新检查等待，已经过去的时间立即执行]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use chrono::{Utc, TimeZone};
+    ///
+    /// let once = timer::spawn_once_at(Utc.ymd(2030,1,1).and_hms(0,0,0),|id| {
+    ///         println!("happy new year:{}",id);
+    ///     }).unwrap();
+    /// once.cancel();
+    /// ```
+    pub fn spawn_once_at<Tz,T,F>(at:DateTime<Tz>, f:F) -> TResult<OnceHandle<T>>
+        where
+            Tz: TimeZone,
+            F: FnOnce(u64) -> T + Send + 'static,
+            T: Send + 'static,
+    {
+        DEFAULT.spawn_once_at(at,f)
+    }
+
+    /// create a new ticker aligned to wall-clock boundaries [创建一个按时钟边界对齐的计时器任务]
+    ///
+    /// boundaries are counted from local midnight, use the date format `Every 5m Epoch`
+    /// with `spawn_with` to count them from the unix epoch
+    /// [边界从本地零点开始计算，需要从unix纪元计算时使用日期格式 `Every 5m Epoch`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use std::time;
+    ///
+    /// // 每5分钟的整点执行，:00 :05 :10 ... fires at :00 :05 :10 ...
+    /// timer::spawn_aligned(time::Duration::from_secs(300),time::Duration::from_secs(0),0,|id| {
+    ///         println!("flush stats:{}",id);
+    ///     }).unwrap();
+    /// ```
+    pub fn spawn_aligned(period:time::Duration, offset:time::Duration, limit:impl Into<RunLimit>, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_aligned(period,offset,limit,f)
+    }
+
+    /// create a new action with schedule and options [通过执行计划和可选配置创建一个任务]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use grapeTimerR::schedule::schedule::{Schedule, TaskOptions};
+    /// use std::time;
+    ///
+    /// let opts = TaskOptions{ tags: vec![String::from("player")], ..TaskOptions::default() };
+    /// timer::spawn_with(Schedule::Tick(time::Duration::from_secs(5)),0,opts.clone(),|id| {
+    ///         println!("buff tick:{}",id);
+    ///     }).unwrap();
+    /// timer::spawn_with(Schedule::from("Day 00:00:00"),0,opts,|id| {
+    ///         println!("daily reset:{}",id);
+    ///     }).unwrap();
+    /// ```
+    pub fn spawn_with(schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_with(schedule,limit,opts,f)
+    }
+
+    /// create a new fallible action [创建一个可失败的任务]
+    ///
+    /// a failed run is retried by `TaskOptions::retry` before the next regular slot,
+    /// then handled by `TaskOptions::failure_policy`
+    /// [失败的执行按重试策略在下一次正常执行前重试，之后按失败策略处理]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use grapeTimerR::schedule::schedule::{Schedule, TaskOptions, RetryPolicy, Backoff};
+    /// use std::time;
+    ///
+    /// let opts = TaskOptions{
+    ///         retry: Some(RetryPolicy{
+    ///             max_attempts: 5,
+    ///             backoff: Backoff::Linear(time::Duration::from_secs(120)),
+    ///             jitter: time::Duration::from_secs(30),
+    ///         }),
+    ///         ..TaskOptions::default()
+    ///     };
+    /// timer::spawn_fallible(Schedule::from("Day 03:00:00"),0,opts,|id| -> Result<(),String> {
+    ///         println!("nightly settlement:{}",id);
+    ///         Ok(())
+    ///     }).unwrap();
+    /// ```
+    pub fn spawn_fallible<E:fmt::Display>(schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f: impl Fn(u64) -> Result<(),E> + Send+Sync + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_fallible(schedule,limit,opts,f)
+    }
+
+    /// create a new action receiving the execution context [创建一个接收执行上下文的任务]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use grapeTimerR::schedule::schedule::{Schedule, TaskOptions};
+    /// use std::time;
+    ///
+    /// timer::spawn_ctx(Schedule::Tick(time::Duration::from_secs(1)),0,TaskOptions::default(),|ctx| -> Result<(),String> {
+    ///         println!("id:{} run:{} late ms:{}",ctx.id,ctx.run_index,ctx.lateness.as_millis());
+    ///         if ctx.run_index >= 10 {
+    ///             ctx.stop();
+    ///         }
+    ///         Ok(())
+    ///     }).unwrap();
+    /// ```
+    pub fn spawn_ctx<E:fmt::Display>(schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f: impl Fn(&ExecContext) -> Result<(),E> + Send+Sync + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_ctx(schedule,limit,opts,f)
+    }
+
+    /// register a handler that persisted jobs bind to by name [注册可持久化任务按名字绑定的处理函数]
+    ///
+    /// Register handlers before `init_schedule`, so the stored jobs can be restored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use grapeTimerR::schedule::schedule::{Schedule, TaskOptions};
+    ///
+    /// timer::register_handler("daily_reset",|ctx| -> Result<(),String> {
+    ///         println!("reset id:{} run:{}",ctx.id,ctx.run_index);
+    ///         Ok(())
+    ///     }).unwrap();
+    /// timer::spawn_job("daily_reset",Schedule::from("Day 05:00:00"),0,TaskOptions::default()).unwrap();
+    /// ```
+    pub fn register_handler<E:fmt::Display>(name:&str,f: impl Fn(&ExecContext) -> Result<(),E> + Send+Sync + 'static) -> TResult<()> {
+        DEFAULT.register_handler(name,f)
+    }
+
+    /// create a job running a registered handler, kept in the job store [创建执行已注册处理函数的任务，并保存到任务存储]
+    pub fn spawn_job(handler:&str,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions) -> TResult<TaskHandle> {
+        DEFAULT.spawn_job(handler,schedule,limit,opts)
     }
 
     /// create a new trait ticker action [创建一个Trait模式计时器任务]
@@ -185,7 +402,8 @@ pub mod timer {
     /// # Examples
     ///
     /// ```
-    /// use grapeTimerR::schedule::schedule::TaskAction;
+    /// use grapeTimerR::schedule::schedule::{TaskAction, RunLimit};
+    /// use grapeTimerR::timer;
     /// use std::sync::Arc;
     ///
     ///
@@ -196,7 +414,7 @@ pub mod timer {
     /// impl TaskAction for ExempleAction {
     ///     // 实际执行的代码段
     ///     // Code snippet executed
-    ///     fn execute(&self, id: u64) {
+    ///     fn execute(&self, _id: u64) {
     ///         println!("on trait struct:{}",chrono::Local::now().to_rfc2822());
     ///     }
     ///
@@ -213,32 +431,31 @@ pub mod timer {
     ///         return 5000;
     ///     }
     ///
-    ///     // 这里需要自定义ID或将其设置为一个组的ID，所以停止任务会停止这个组
-    ///     // Here you need to customize the ID or set it to the GroupId or TaskType Id,
-    ///     // so stopping the task will stop the group
+    ///     // 这里需要自定义ID，ID在调度器内必须唯一，重复的ID会返回错误
+    ///     // Here you need to customize the ID, it must be unique in the scheduler,
+    ///     // spawning a duplicate id returns an error
     ///     fn id(&self) -> u64 {
     ///         return 18888;
     ///     }
     ///
-    ///     // 循环的次数
-    ///     fn loop_count(&self) -> i32 {
-    ///        return 15;
+    ///     // 执行的次数或截止时间
+    ///     // How many times or until when the task runs
+    ///     fn run_limit(&self) -> RunLimit {
+    ///        RunLimit::Times(15)
+    ///     }
+    ///
+    ///     // 可选，任务所属的分组标签，可以按标签停止或暂停一组任务
+    ///     // Optional, tags of the task, a group of tasks can be stopped or paused by tag
+    ///     fn tags(&self) -> Vec<String> {
+    ///        vec![String::from("exemple")]
     ///     }
     /// }
     ///    // 使用trait任务，可以简化部分实际逻辑
     ///    // Using trait tasks can simplify part of the actual logic
     ///    timer::spawn_trait(Arc::new(ExempleAction{}));
     /// ```
-    pub fn spawn_trait(ft:Arc<dyn TaskAction>) -> TResult<u64> {
-        let r = Inter.thread_pool.lock();
-        match r {
-            Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) },
-            Ok(mut v) => {
-                let taskId = ft.id();
-                v.spawn(ft);
-                Ok(taskId)
-            }
-        }
+    pub fn spawn_trait(ft:Arc<dyn TaskAction>) -> TResult<TaskHandle> {
+        DEFAULT.spawn_trait(ft)
     }
 
     /// create a new ticker action [创建一个计时器任务]
@@ -247,21 +464,102 @@ pub mod timer {
     ///
     /// ```
     /// use grapeTimerR::timer;
-    /// timer::spawn_date("day 19:30:00",1,|id| {
+    /// timer::spawn_date("day 19:30:00",1,|_id| {
     ///        println!("on date:{}",chrono::Local::now().to_rfc2822());
     /// });
     /// ```
-    pub fn spawn_date(dateformate:&str, loopCount:i32, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<u64> {
-        let task_action =  ClosuresAction::new(dateformate, next_uuid(), loopCount, time::Duration::from_secs(0), f);
-        let r = Inter.thread_pool.lock();
-        match r {
-            Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) },
-            Ok(mut v) => {
-                let task_id = task_action.id();
-                v.spawn(Arc::new(task_action));
-                Ok(task_id)
-            }
-        }
+    pub fn spawn_date(dateformate:&str, limit:impl Into<RunLimit>, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_date(dateformate,limit,f)
+    }
+
+    /// create a new date action with a stateful closure [创建一个使用可变闭包的日期任务]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    ///
+    /// let mut days = 0;
+    /// timer::spawn_date_mut("day 19:30:00",0,move |_id| {
+    ///        days += 1;
+    ///        println!("on date:{} days:{}",chrono::Local::now().to_rfc2822(),days);
+    /// }).unwrap();
+    /// ```
+    pub fn spawn_date_mut(dateformate:&str, limit:impl Into<RunLimit>, f: impl FnMut(u64) + Send + 'static) -> TResult<TaskHandle> {
+        DEFAULT.spawn_date_mut(dateformate,limit,f)
+    }
+
+    /// create a new async ticker action, the future is awaited in the scheduler
+    /// [创建一个异步计时器任务，调度器会等待Future执行完成]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use std::time;
+    ///
+    /// timer::spawn_async_ticker(time::Duration::from_millis(5000),2,|id| async move {
+    ///         println!("on async ticker:{}",id);
+    ///     }).unwrap();
+    /// ```
+    pub fn spawn_async_ticker<F,Fut>(tick:time::Duration, limit:impl Into<RunLimit>, f:F) -> TResult<TaskHandle>
+        where
+            F: Fn(u64) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = ()> + Send + 'static,
+    {
+        DEFAULT.spawn_async_ticker(tick,limit,f)
+    }
+
+    /// create a new async date action [创建一个异步日期任务]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// timer::spawn_async_date("Day 03:00:00",0,|id| async move {
+    ///        println!("on async date:{}",id);
+    /// }).unwrap();
+    /// ```
+    pub fn spawn_async_date<F,Fut>(dateformate:&str, limit:impl Into<RunLimit>, f:F) -> TResult<TaskHandle>
+        where
+            F: Fn(u64) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = ()> + Send + 'static,
+    {
+        DEFAULT.spawn_async_date(dateformate,limit,f)
+    }
+
+    /// create a new async action with schedule and options [通过执行计划和可选配置创建一个异步任务]
+    pub fn spawn_async_with<F,Fut>(schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f:F) -> TResult<TaskHandle>
+        where
+            F: Fn(u64) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = ()> + Send + 'static,
+    {
+        DEFAULT.spawn_async_with(schedule,limit,opts,f)
+    }
+
+    /// create a new fallible async action [创建一个可失败的异步任务]
+    pub fn spawn_async_fallible<F,Fut,E>(schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f:F) -> TResult<TaskHandle>
+        where
+            F: Fn(u64) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<(),E>> + Send + 'static,
+            E: fmt::Display,
+    {
+        DEFAULT.spawn_async_fallible(schedule,limit,opts,f)
+    }
+
+    /// create a new async action receiving the execution context [创建一个接收执行上下文的异步任务]
+    pub fn spawn_async_ctx<F,Fut,E>(schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f:F) -> TResult<TaskHandle>
+        where
+            F: Fn(ExecContext) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<(),E>> + Send + 'static,
+            E: fmt::Display,
+    {
+        DEFAULT.spawn_async_ctx(schedule,limit,opts,f)
+    }
+
+    /// create a new async trait action [创建一个异步Trait模式任务]
+    pub fn spawn_async_trait(ft:Arc<dyn AsyncTaskAction>) -> TResult<TaskHandle> {
+        DEFAULT.spawn_async_trait(ft)
     }
 
      /// Used to use asynchronous tasks in Timer [用于在代码中使用异步任务]
@@ -274,19 +572,12 @@ pub mod timer {
      ///     println!("block on");
      /// });
      /// ```
-    pub fn block_on_rt<F>(future: F) -> TResult<()>
+    pub fn block_on_rt<F>(future: F) -> TResult<F::Output>
          where
              F: Future,
              F::Output: Send + 'static,
      {
-         let r =Inter.thread_pool.lock();
-         match r {
-             Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) },
-             Ok(mut v) => {
-                 v.block_on(future);
-                 Ok(())
-             }
-         }
+         DEFAULT.block_on_rt(future)
      }
 
     /// Used to use asynchronous tasks in Timer [用于在代码中使用异步任务]
@@ -304,33 +595,142 @@ pub mod timer {
             F: Future + Send + 'static,
             F::Output: Send + 'static,
     {
-        let r =Inter.thread_pool.lock();
-        match r {
-            Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) },
-            Ok(mut v) => {
-                v.spawn_rt(future);
-                Ok(())
-            }
-        }
+        DEFAULT.spawn_rt(future)
     }
 
-    /// stop a ticker action [停止一个计时器任务]
+    /// stop a ticker action [停止一个计时器任务，正在等待的任务会被立即唤醒]
+    ///
+    /// returns an error if the id is unknown [ID不存在时返回错误]
     ///
     /// # Examples
     ///
     /// ```
     /// use grapeTimerR::timer::stop_ticker;
-    /// stop_ticker(123);
+    /// assert!(stop_ticker(123).is_err());
     /// ```
     pub fn stop_ticker(id:u64) -> TResult<()> {
-        let r = Inter.thread_pool.lock();
-        match r {
-            Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) },
-            Ok(mut v) => {
-                let r = v.stop_task(id)?;
-                Ok(r)
-            }
-        }
+        DEFAULT.stop_ticker(id)
+    }
+
+    /// pause a ticker action [暂停一个计时器任务]
+    ///
+    /// interval tasks keep the remaining time, date tasks work out the next fire again when resumed
+    /// [间隔任务保留剩余时间，日期任务在恢复时重新计算下一次执行时间]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use std::time;
+    ///
+    /// let handle = timer::spawn_ticker(time::Duration::from_secs(60),0,|_id| {}).unwrap();
+    /// timer::pause_ticker(handle.id()).unwrap();
+    /// timer::resume_ticker(handle.id()).unwrap();
+    /// ```
+    pub fn pause_ticker(id:u64) -> TResult<()> {
+        DEFAULT.pause_ticker(id)
+    }
+
+    /// resume a paused ticker action [恢复一个暂停的计时器任务]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer::resume_ticker;
+    /// assert!(resume_ticker(123).is_err());
+    /// ```
+    pub fn resume_ticker(id:u64) -> TResult<()> {
+        DEFAULT.resume_ticker(id)
+    }
+
+    /// change the interval or date format of a running task [修改一个运行中任务的间隔或日期格式]
+    ///
+    /// the task keeps its id and run count, the next fire is worked out from now
+    /// [任务保留ID和执行次数，下一次执行时间从现在开始计算]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::{timer, schedule::schedule::Schedule};
+    /// use std::time;
+    ///
+    /// let handle = timer::spawn_ticker(time::Duration::from_secs(60),0,|_id| {}).unwrap();
+    /// timer::reschedule(handle.id(),Schedule::from("Week 1 05:00:00")).unwrap();
+    /// timer::reschedule(handle.id(),Schedule::Tick(time::Duration::from_secs(30))).unwrap();
+    /// ```
+    pub fn reschedule(id:u64,schedule:Schedule) -> TResult<()> {
+        DEFAULT.reschedule(id,schedule)
+    }
+
+    /// list all registered tasks, finished and cancelled tasks are removed [列出所有已注册的任务，结束的任务会被移除]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    ///
+    /// timer::spawn_date("Day 05:00:00",0,|_id| {}).unwrap();
+    /// for info in timer::list_tasks().unwrap() {
+    ///     println!("id:{} schedule:{} runs:{} next:{:?} status:{:?}",
+    ///              info.id,info.schedule,info.run_count,info.next_run,info.status);
+    /// }
+    /// ```
+    pub fn list_tasks() -> TResult<Vec<TaskInfo>> {
+        DEFAULT.list_tasks()
+    }
+
+    /// inspect a registered task [查询一个已注册任务的信息]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use grapeTimerR::schedule::schedule::RunLimit;
+    /// use std::time;
+    ///
+    /// let handle = timer::spawn_ticker(time::Duration::from_secs(60),3,|_id| {}).unwrap();
+    /// let info = timer::task_info(handle.id()).unwrap();
+    /// assert_eq!(info.run_limit,RunLimit::Times(3));
+    /// assert_eq!(info.runs_left,Some(3));
+    /// ```
+    pub fn task_info(id:u64) -> TResult<TaskInfo> {
+        DEFAULT.task_info(id)
+    }
+
+    /// stop all tasks with the tag [停止分组内的所有任务，返回停止的数量]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use grapeTimerR::schedule::schedule::{Schedule, TaskOptions};
+    /// use std::time;
+    ///
+    /// let opts = TaskOptions{ tags: vec![String::from("doc_group")], ..TaskOptions::default() };
+    /// timer::spawn_with(Schedule::Tick(time::Duration::from_secs(5)),0,opts.clone(),|_id| {}).unwrap();
+    /// timer::spawn_with(Schedule::Tick(time::Duration::from_secs(9)),0,opts,|_id| {}).unwrap();
+    ///
+    /// assert_eq!(timer::list_group("doc_group").unwrap().len(),2);
+    /// assert_eq!(timer::pause_group("doc_group").unwrap(),2);
+    /// assert_eq!(timer::stop_group("doc_group").unwrap(),2);
+    /// ```
+    pub fn stop_group(tag:&str) -> TResult<usize> {
+        DEFAULT.stop_group(tag)
+    }
+
+    /// pause all tasks with the tag [暂停分组内的所有任务]
+    pub fn pause_group(tag:&str) -> TResult<usize> {
+        DEFAULT.pause_group(tag)
+    }
+
+    /// resume all tasks with the tag [恢复分组内的所有任务]
+    pub fn resume_group(tag:&str) -> TResult<usize> {
+        DEFAULT.resume_group(tag)
+    }
+
+    /// list all tasks with the tag [列出分组内的所有任务]
+    pub fn list_group(tag:&str) -> TResult<Vec<TaskInfo>> {
+        DEFAULT.list_group(tag)
     }
 
     /// wait main thread forever [永远阻塞主线程，非必须调用]
@@ -1,13 +1,133 @@
+#[allow(clippy::module_inception)]
 pub mod parsers {
-    use std::{time,fmt};
-    use chrono::{Local, DateTime, FixedOffset, ParseError, NaiveTime, NaiveDate, Datelike, TimeZone, Timelike, Utc, Duration};
+    use chrono::{Local, DateTime, NaiveDate, NaiveTime, Datelike, TimeZone, Timelike, Utc, Duration, FixedOffset};
     use crate::errors::errors::{TError, TErrorKind, TResult};
-    use std::ops::Add;
+    use crate::clock::clock::{Clock, SystemClock};
 
     pub struct DateParser {
         pub action:String, // 具体类型
         pub clock:String, // 时间日期
         pub day_time:i8, // week = 0~6,month = 1~31
+        pub aligned:Option<Aligned>, // every = 对齐时钟边界的间隔
+    }
+
+    /// where the boundaries of an aligned interval start [对齐间隔的起点]
+    ///
+    /// `Midnight` follows the time zone the next time is computed in, local time for
+    /// `parser_next` and UTC for `parser_nextUtc`. `LocalMidnight` and `MidnightAt` pin the
+    /// day to one zone whichever function is used.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AlignAnchor {
+        Midnight,                // 所在时区的零点
+        LocalMidnight,           // 本地时区的零点
+        MidnightAt(FixedOffset), // 指定时差的零点，UTC为+00:00
+        Epoch,                   // unix纪元
+    }
+
+    /// interval aligned to wall-clock boundaries [按时钟边界对齐的间隔]
+    ///
+    /// `Every 5m` fires at :00, :05, :10 ..., `Every 1h +15m` fires at a quarter past every hour.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Aligned {
+        pub period:Duration,
+        pub offset:Duration,
+        pub anchor:AlignAnchor,
+    }
+
+    impl Aligned {
+        pub fn new(period:Duration,offset:Duration,anchor:AlignAnchor) -> TResult<Aligned> {
+            if period <= Duration::zero() || offset < Duration::zero() {
+                return Err(TError::new(TErrorKind::BadFormat));
+            }
+            Ok(Aligned{ period, offset, anchor })
+        }
+
+        /// next boundary strictly after now, in the time zone of now [计算now之后的下一个边界，使用now所在的时区]
+        pub fn next_after<Tz:TimeZone>(&self,now:&DateTime<Tz>) -> DateTime<Tz> {
+            let period = self.period.num_milliseconds();
+            let offset = self.offset.num_milliseconds() % period;
+            match self.anchor {
+                AlignAnchor::Epoch => {
+                    let since = now.timestamp_millis() - offset;
+                    let next = (since.div_euclid(period) + 1) * period + offset;
+                    now.timezone().timestamp_millis(next)
+                }
+                AlignAnchor::Midnight => { midnight_next(now,period,offset) }
+                AlignAnchor::LocalMidnight => {
+                    midnight_next(&now.with_timezone(&Local),period,offset).with_timezone(&now.timezone())
+                }
+                AlignAnchor::MidnightAt(tz) => {
+                    midnight_next(&now.with_timezone(&tz),period,offset).with_timezone(&now.timezone())
+                }
+            }
+        }
+    }
+
+    // 每天从零点重新开始计算，单位毫秒，夏令时切换的一天按实际的长度计算
+    fn midnight_next<Tz:TimeZone>(now:&DateTime<Tz>,period:i64,offset:i64) -> DateTime<Tz> {
+        let date = now.naive_local().date();
+        let midnight = day_start(&now.timezone(),date);
+        let since = (now.clone() - midnight.clone()).num_milliseconds() - offset;
+        let next = midnight + Duration::milliseconds((since.div_euclid(period) + 1) * period + offset);
+        let tomorrow = day_start(&now.timezone(),date.succ());
+        if next >= tomorrow {
+            tomorrow + Duration::milliseconds(offset)
+        }else {
+            next
+        }
+    }
+
+    // 一天的开始，零点不存在时(夏令时从零点开始)取这一天最早存在的时间
+    fn day_start<Tz:TimeZone>(tz:&Tz,date:NaiveDate) -> DateTime<Tz> {
+        let midnight = date.and_hms(0,0,0);
+        (0..=24 * 60).find_map(|m| tz.from_local_datetime(&(midnight + Duration::minutes(m))).earliest())
+            .unwrap_or_else(|| tz.from_utc_datetime(&midnight))
+    }
+
+    // Midnight+08:00 Midnight-05:30
+    fn parser_offset(value:&str) -> TResult<FixedOffset> {
+        let sign = match value.chars().next() {
+            Some('+') => { 1 }
+            Some('-') => { -1 }
+            _ => { return Err(TError::new(TErrorKind::BadFormat)) }
+        };
+        let hm = value[1..].split(':').map(|v| v.parse::<i32>()).collect::<Vec<_>>();
+        match hm.as_slice() {
+            [Ok(h),Ok(m)] if *h <= 23 && *m <= 59 => {
+                FixedOffset::east_opt(sign * (h * 3600 + m * 60)).ok_or_else(|| TError::new(TErrorKind::BadFormat))
+            }
+            _ => { Err(TError::new(TErrorKind::BadFormat)) }
+        }
+    }
+
+    // 间隔和偏移最长一年
+    const SPAN_LIMIT_MS:i64 = 366 * 24 * 3600 * 1000;
+
+    // 5m 30s 1h 1d 500ms
+    fn parser_span(value:&str) -> TResult<Duration> {
+        let split = value.find(|c:char| !c.is_ascii_digit()).unwrap_or(value.len());
+        let count:i64 = match value[..split].parse() {
+            Ok(v) => { v }
+            Err(_) => { return Err(TError::new(TErrorKind::BadFormat)) }
+        };
+        let unit:i64 = match value[split..].to_lowercase().as_str() {
+            "ms" => { 1 }
+            "s" => { 1000 }
+            "m" => { 60 * 1000 }
+            "h" => { 3600 * 1000 }
+            "d" => { 24 * 3600 * 1000 }
+            _ => { return Err(TError::new(TErrorKind::BadFormat)) }
+        };
+        match count.checked_mul(unit) {
+            Some(v) if v <= SPAN_LIMIT_MS => { Ok(Duration::milliseconds(v)) }
+            _ => { Err(TError::new(TErrorKind::BadFormat)) }
+        }
+    }
+
+    impl Default for DateParser {
+        fn default() -> Self {
+            DateParser::new()
+        }
     }
 
     impl DateParser {
@@ -15,17 +135,18 @@ pub mod parsers {
             DateParser{
                 action: String::from(""),
                 clock: String::from(""),
-                day_time: 0
+                day_time: 0,
+                aligned: None,
             }
         }
 
         pub fn parser(&mut self, date_format:&str) -> TResult<()> {
-            let mut date_split = date_format.split(" ").collect::<Vec<&str>>();
+            let date_split = date_format.split(' ').collect::<Vec<&str>>();
             if date_split.len() < 2 {
                 return Err(TError::new(TErrorKind::BadFormat)); // 格式错误
             }
 
-            self.action = String::from(date_split[0].to_lowercase());
+            self.action = date_split[0].to_lowercase();
             match self.action.as_str() {
                 "day" => {
                     self.clock = String::from(date_split[1])
@@ -39,9 +160,35 @@ pub mod parsers {
                     let rv = date_split[1].parse();
                     match rv {
                         Ok(value) => { self.day_time = value }
-                        Err(e) => { return Err(TError::new(TErrorKind::Other(e.to_string().clone()))) }  // 格式错误
+                        Err(e) => { return Err(TError::new(TErrorKind::Other(e.to_string()))) }  // 格式错误
                     }
                 }
+                "every" => {
+                    // Every 5m [+30s] [Midnight|Local|Utc|Midnight+08:00|Epoch]
+                    let period = parser_span(date_split[1])?;
+                    let mut offset = Duration::zero();
+                    let mut anchor = AlignAnchor::Midnight;
+                    for v in date_split[2..].iter() {
+                        if let Some(span) = v.strip_prefix('+') {
+                            offset = parser_span(span)?;
+                            continue;
+                        }
+                        let v = v.to_lowercase();
+                        anchor = match v.as_str() {
+                            "midnight" => { AlignAnchor::Midnight }
+                            "local" => { AlignAnchor::LocalMidnight }
+                            "utc" => { AlignAnchor::MidnightAt(FixedOffset::east(0)) }
+                            "epoch" => { AlignAnchor::Epoch }
+                            _ => {
+                                match v.strip_prefix("midnight") {
+                                    Some(tz) => { AlignAnchor::MidnightAt(parser_offset(tz)?) }
+                                    None => { return Err(TError::new(TErrorKind::BadFormat)) }
+                                }
+                            }
+                        };
+                    }
+                    self.aligned = Some(Aligned::new(period,offset,anchor)?);
+                }
                 _ => {}
             }
             Ok(())
@@ -49,20 +196,23 @@ pub mod parsers {
     }
 
     // get month days
+    #[allow(non_snake_case)]
     pub fn getMonthDay(year:i32,month:i32) -> Option<i32> {
         let m_wday = vec![31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
         if month == 2 && (year%4) == 0 && ( year % 100 != 0 || year % 400 == 0) {
             Some(29)
         }else {
             let index = (month - 1) as usize;
-           let r = m_wday.get(index);
-            match r {
-                Some(v) => { Some(*v) }
-                None => None
-            }
+            m_wday.get(index).copied()
         }
     }
 
+    // 某个月之后第count个月的年份和月份
+    fn month_after(year:i32,month:u32,count:u32) -> (i32,u32) {
+        let index = year * 12 + (month - 1 + count) as i32;
+        (index.div_euclid(12),index.rem_euclid(12) as u32 + 1)
+    }
+
     fn next_date_time(year:i32, month:u32, day:u32, time_format:&str) -> TResult<DateTime<Local>> {
         let vt = NaiveTime::parse_from_str(time_format, "%T");
         match vt {
@@ -77,6 +227,7 @@ pub mod parsers {
         }
     }
 
+    #[allow(non_snake_case)]
     fn next_date_timeUtc(year:i32, month:u32, day:u32, time_format:&str) -> TResult<DateTime<Utc>> {
         let vt = NaiveTime::parse_from_str(time_format, "%T");
         match vt {
@@ -92,8 +243,8 @@ pub mod parsers {
     }
 
     // time 00:00:00 get now time
-    fn atNowTime(time_format:&str) -> TResult<DateTime<Local>> {
-        let now_time = Local::now();
+    #[allow(non_snake_case)]
+    fn atNowTime(time_format:&str,now_time:&DateTime<Local>) -> TResult<DateTime<Local>> {
         let vt = NaiveTime::parse_from_str(time_format, "%T");
         match vt {
             Ok(time_tt) => {
@@ -107,8 +258,8 @@ pub mod parsers {
         }
     }
 
-    fn atUtcNowTime(time_format:&str) -> TResult<DateTime<Utc>> {
-        let now_time = Utc::now();
+    #[allow(non_snake_case)]
+    fn atUtcNowTime(time_format:&str,now_time:&DateTime<Utc>) -> TResult<DateTime<Utc>> {
         let vt = NaiveTime::parse_from_str(time_format, "%T");
         match vt {
             Ok(time_tt) => {
@@ -129,29 +280,54 @@ pub mod parsers {
     /// Week 1 05:00:00 What time of the week, week flag 0~6 [每周几的几点]
     /// Month 1 05:00:00 What time of the Month,month flag 1~31 [每月几日几点，不足跳过]
     /// Skip date if there is no such date in this month [该月如果没这个日期，则跳过该月]
+    /// Every 5m [+30s] [Midnight|Epoch] aligned interval, from local midnight by default [按时钟边界对齐的间隔，默认从本地零点开始]
     /// # Examples
     ///
     /// ```
-    /// use grapeTimerR::parsers;
-    /// let next_day = parsers::parser_next("Day 05::00:00").unwrap();
+    /// use grapeTimerR::parsers::parsers;
+    /// let next_day = parsers::parser_next("Day 05:00:00").unwrap();
     /// let next_day2 = parsers::parser_next("Week 1 05:00:00").unwrap();
+    /// let next_flush = parsers::parser_next("Every 5m").unwrap();
     /// ```
+    #[allow(non_snake_case)]
     pub fn parser_next(timeStr:&str) -> TResult<chrono::DateTime<Local>> {
+        parser_next_with(timeStr,&SystemClock)
+    }
+
+    /// parser date format from the time of a clock [从时钟的当前时间分析出下一次的运行时间]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::parsers::parsers;
+    /// use grapeTimerR::clock::clock::ManualClock;
+    /// use chrono::{Local, TimeZone};
+    ///
+    /// let clock = ManualClock::new(Local.ymd(2026,3,2).and_hms(6,0,0));
+    /// let next_day = parsers::parser_next_with("Day 05:00:00",&clock).unwrap();
+    /// assert_eq!(next_day,Local.ymd(2026,3,3).and_hms(5,0,0));
+    /// ```
+    #[allow(non_snake_case)]
+    pub fn parser_next_with(timeStr:&str,clock:&dyn Clock) -> TResult<chrono::DateTime<Local>> {
         let mut date_pv = DateParser::new();
         date_pv.parser(timeStr)?; //分析分析数据
 
-        let nowTime = Local::now();
+        let nowTime = clock.now();
+
+        if let Some(aligned) = date_pv.aligned {
+            return Ok(aligned.next_after(&nowTime));
+        }
 
         if "day" == date_pv.action {
-            let mut atTime = atNowTime(&date_pv.clock)?;
+            let mut atTime = atNowTime(&date_pv.clock,&nowTime)?;
             if nowTime.timestamp() >= atTime.timestamp() {
                 atTime = atTime + Duration::days(1);
             }
             return Ok(atTime);
         }else if "week" == date_pv.action {
-            let mut atTime = atNowTime(&date_pv.clock)?;
-            if date_pv.day_time >= 7 || date_pv.day_time < 0 {
-               return Err(TError::new(TErrorKind::WeekDay));
+            let mut atTime = atNowTime(&date_pv.clock,&nowTime)?;
+            if !(0..7).contains(&date_pv.day_time) {
+                return Err(TError::new(TErrorKind::WeekDay));
             }
 
             let weekDayNow = nowTime.weekday().num_days_from_sunday() as i8;
@@ -160,31 +336,30 @@ pub mod parsers {
                 weekOffset += 7;
             }
 
-            if weekOffset != 0 ||nowTime.timestamp() >= atTime.timestamp() {
-                atTime = atTime +Duration::days(weekOffset as i64);
+            // 今天的时间已经过了，顺延到下周
+            if weekOffset == 0 && nowTime.timestamp() >= atTime.timestamp() {
+                weekOffset = 7;
             }
+            atTime = atTime + Duration::days(weekOffset as i64);
 
             return Ok(atTime);
         }else if "month" == date_pv.action {
-            let maxDay = getMonthDay(nowTime.year(),nowTime.month() as i32).unwrap();
-            if date_pv.day_time as i32 > maxDay {
+            if !(1..=31).contains(&date_pv.day_time) {
                 return Err(TError::new(TErrorKind::DateOverflow));
             }
 
-            // 主动计算下一个日期
-            let mut nextTime = next_date_time(nowTime.year(),
-                                              nowTime.month(),date_pv.day_time as u32,
-                                              &date_pv.clock)?;
-
-            if nowTime.timestamp() >= nextTime.timestamp() {
-                nextTime = next_date_time(nowTime.year(),
-                                          nowTime.month()+1,date_pv.day_time as u32,
-                                          &date_pv.clock)?;
+            // 从本月开始找下一个日期，没有这一天的月份跳过，12月之后是下一年
+            for count in 0..12 {
+                let (year,month) = month_after(nowTime.year(),nowTime.month(),count);
+                if date_pv.day_time as i32 > getMonthDay(year,month as i32).unwrap_or(0) {
+                    continue;
+                }
+                let nextTime = next_date_time(year,month,date_pv.day_time as u32,&date_pv.clock)?;
+                if nowTime.timestamp() < nextTime.timestamp() {
+                    return Ok(nextTime);
+                }
             }
-
-            return Ok(nextTime);
-        }else {
-            return Err(TError::new(TErrorKind::BadFormat));
+            return Err(TError::new(TErrorKind::DateOverflow));
         }
 
         Err(TError::new(TErrorKind::BadFormat))
@@ -200,10 +375,11 @@ pub mod parsers {
     /// # Examples
     ///
     /// ```
-    /// use grapeTimerR::parsers;
-    /// let next_day = parsers::parser_timestamp("Day 05::00:00").unwrap();
+    /// use grapeTimerR::parsers::parsers;
+    /// let next_day = parsers::parser_timestamp("Day 05:00:00").unwrap();
     /// let next_day2 = parsers::parser_timestamp("Week 1 05:00:00").unwrap();
     /// ```
+    #[allow(non_snake_case)]
     pub fn parser_timestamp(timeStr:&str) -> TResult<i64> {
         let date_now = parser_next(timeStr)?;
         Ok(date_now.timestamp())
@@ -216,28 +392,41 @@ pub mod parsers {
     /// Week 1 05:00:00 What time of the week, week flag 0~6 [每周几的几点]
     /// Month 1 05:00:00 What time of the Month,month flag 1~31 [每月几日几点，不足跳过]
     /// Skip date if there is no such date in this month [该月如果没这个日期，则跳过该月]
+    /// Every 5m [+30s] [Midnight|Epoch] aligned interval, from utc midnight by default [按时钟边界对齐的间隔，默认从UTC零点开始]
     /// # Examples
     ///
     /// ```
-    /// use grapeTimerR::parsers;
-    /// let next_day = parsers::parser_nextUtc("Day 05::00:00").unwrap();
+    /// use grapeTimerR::parsers::parsers;
+    /// let next_day = parsers::parser_nextUtc("Day 05:00:00").unwrap();
     /// let next_day2 = parsers::parser_nextUtc("Week 1 05:00:00").unwrap();
+    /// let next_flush = parsers::parser_nextUtc("Every 1h +15m").unwrap();
     /// ```
+    #[allow(non_snake_case)]
     pub fn parser_nextUtc(timeStr:&str) -> TResult<chrono::DateTime<Utc>> {
+        parser_nextUtc_with(timeStr,&SystemClock)
+    }
+
+    /// parser date format from the utc time of a clock [从时钟的当前UTC时间分析出下一次的运行时间]
+    #[allow(non_snake_case)]
+    pub fn parser_nextUtc_with(timeStr:&str,clock:&dyn Clock) -> TResult<chrono::DateTime<Utc>> {
         let mut date_pv = DateParser::new();
         date_pv.parser(timeStr)?; //分析分析数据
 
-        let nowTime = Utc::now();
+        let nowTime = clock.now_utc();
+
+        if let Some(aligned) = date_pv.aligned {
+            return Ok(aligned.next_after(&nowTime));
+        }
 
         if "day" == date_pv.action {
-            let mut atTime = atUtcNowTime(&date_pv.clock)?;
+            let mut atTime = atUtcNowTime(&date_pv.clock,&nowTime)?;
             if nowTime.timestamp() >= atTime.timestamp() {
                 atTime = atTime + Duration::days(1);
             }
             return Ok(atTime);
         }else if "week" == date_pv.action {
-            let mut atTime = atUtcNowTime(&date_pv.clock)?;
-            if date_pv.day_time >= 7 || date_pv.day_time < 0 {
+            let mut atTime = atUtcNowTime(&date_pv.clock,&nowTime)?;
+            if !(0..7).contains(&date_pv.day_time) {
                 return Err(TError::new(TErrorKind::WeekDay));
             }
 
@@ -247,31 +436,30 @@ pub mod parsers {
                 weekOffset += 7;
             }
 
-            if weekOffset != 0 ||nowTime.timestamp() >= atTime.timestamp() {
-                atTime = atTime +Duration::days(weekOffset as i64);
+            // 今天的时间已经过了，顺延到下周
+            if weekOffset == 0 && nowTime.timestamp() >= atTime.timestamp() {
+                weekOffset = 7;
             }
+            atTime = atTime + Duration::days(weekOffset as i64);
 
             return Ok(atTime);
         }else if "month" == date_pv.action {
-            let maxDay = getMonthDay(nowTime.year(),nowTime.month() as i32).unwrap();
-            if date_pv.day_time as i32 > maxDay {
+            if !(1..=31).contains(&date_pv.day_time) {
                 return Err(TError::new(TErrorKind::DateOverflow));
             }
 
-            // 主动计算下一个日期
-            let mut nextTime = next_date_timeUtc(nowTime.year(),
-                                              nowTime.month(),date_pv.day_time as u32,
-                                              &date_pv.clock)?;
-
-            if nowTime.timestamp() >= nextTime.timestamp() {
-                nextTime = next_date_timeUtc(nowTime.year(),
-                                          nowTime.month()+1,date_pv.day_time as u32,
-                                          &date_pv.clock)?;
+            // 从本月开始找下一个日期，没有这一天的月份跳过，12月之后是下一年
+            for count in 0..12 {
+                let (year,month) = month_after(nowTime.year(),nowTime.month(),count);
+                if date_pv.day_time as i32 > getMonthDay(year,month as i32).unwrap_or(0) {
+                    continue;
+                }
+                let nextTime = next_date_timeUtc(year,month,date_pv.day_time as u32,&date_pv.clock)?;
+                if nowTime.timestamp() < nextTime.timestamp() {
+                    return Ok(nextTime);
+                }
             }
-
-            return Ok(nextTime);
-        }else {
-            return Err(TError::new(TErrorKind::BadFormat));
+            return Err(TError::new(TErrorKind::DateOverflow));
         }
 
         Err(TError::new(TErrorKind::BadFormat))
@@ -287,10 +475,11 @@ pub mod parsers {
     /// # Examples
     ///
     /// ```
-    /// use grapeTimerR::parsers;
-    /// let next_day = parsers::parser_timestampUtc("Day 05::00:00").unwrap();
+    /// use grapeTimerR::parsers::parsers;
+    /// let next_day = parsers::parser_timestampUtc("Day 05:00:00").unwrap();
     /// let next_day2 = parsers::parser_timestampUtc("Week 1 05:00:00").unwrap();
     /// ```
+    #[allow(non_snake_case)]
     pub fn parser_timestampUtc(timeStr:&str) -> TResult<i64> {
         let date_now = parser_nextUtc(timeStr)?;
         Ok(date_now.timestamp())
@@ -300,62 +489,207 @@ pub mod parsers {
     #[test]
     fn test_parser_date() {
         let mut datep = DateParser::new();
-        datep.parser("Day 00:00:00");
+        datep.parser("Day 00:00:00").unwrap();
         assert_eq!(datep.action,"day");
         assert_eq!(datep.clock,"00:00:00");
 
-        datep.parser("Week 1 00:00:00");
+        datep.parser("Week 1 00:00:00").unwrap();
         assert_eq!(datep.action,"week");
         assert_eq!(datep.day_time,1);
         assert_eq!(datep.clock,"00:00:00");
+
+        datep.parser("Every 5m +30s Epoch").unwrap();
+        assert_eq!(datep.action,"every");
+        assert_eq!(datep.aligned,Some(Aligned{ period:Duration::minutes(5), offset:Duration::seconds(30), anchor:AlignAnchor::Epoch }));
+        assert!(DateParser::new().parser("Every 0m").is_err());
+        assert!(DateParser::new().parser("Every 5x").is_err());
+        assert!(DateParser::new().parser("Every 5m Noon").is_err());
+        datep.parser("Every 1h Utc").unwrap();
+        assert_eq!(datep.aligned.map(|v| v.anchor),Some(AlignAnchor::MidnightAt(FixedOffset::east(0))));
+        datep.parser("Every 1h Local").unwrap();
+        assert_eq!(datep.aligned.map(|v| v.anchor),Some(AlignAnchor::LocalMidnight));
+        datep.parser("Every 1h Midnight-05:30").unwrap();
+        assert_eq!(datep.aligned.map(|v| v.anchor),Some(AlignAnchor::MidnightAt(FixedOffset::west(5 * 3600 + 1800))));
+        for v in ["Every 1h Midnight+24:00","Every 1h Midnight08:00","Every 1h Midnight+8"] {
+            assert!(DateParser::new().parser(v).is_err(),"{}",v);
+        }
+
+        // 溢出和超过一年的间隔是格式错误
+        datep.parser("Every 366d +365d").unwrap();
+        assert_eq!(datep.aligned.map(|v| v.period),Some(Duration::days(366)));
+        for v in ["Every 367d","Every 9223372036854775807d","Every 106751991167301d","Every 99999999999999999999ms","Every 5m +9223372036854775807h"] {
+            assert!(matches!(DateParser::new().parser(v).map_err(|e| e.kind().clone()),Err(TErrorKind::BadFormat)),"{}",v);
+        }
     }
 
     #[test]
     fn test_utils() {
-        let gDay = getMonthDay(2009,1);
-        let timeFtm = atNowTime("05:00:00").unwrap();
-
-        println!("day:{}",gDay.unwrap());
-        println!("fmt:{}",timeFtm.format("%Y-%m-%d %H:%M:%S"));
-        println!("unix time:{}",timeFtm.timestamp())
+        assert_eq!(getMonthDay(2009,1),Some(31));
+        assert_eq!(getMonthDay(2024,2),Some(29));
+        assert_eq!(getMonthDay(1900,2),Some(28));
+        assert_eq!(getMonthDay(2000,2),Some(29));
+        assert_eq!(getMonthDay(2009,13),None);
+
+        assert_eq!(month_after(2026,3,0),(2026,3));
+        assert_eq!(month_after(2026,12,1),(2027,1));
+        assert_eq!(month_after(2026,11,14),(2028,1));
+
+        let now = Local.ymd(2026,3,2).and_hms(12,0,0);
+        assert_eq!(atNowTime("05:00:00",&now).unwrap(),Local.ymd(2026,3,2).and_hms(5,0,0));
+        assert!(atNowTime("5 o'clock",&now).is_err());
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::parsers::parsers::{getMonthDay, parser_next, parser_timestamp, parser_nextUtc, parser_timestampUtc};
-    use chrono::Local;
+    use crate::parsers::parsers::{parser_next, parser_timestamp, parser_nextUtc, parser_timestampUtc, parser_next_with, parser_nextUtc_with, Aligned, AlignAnchor, DateParser};
+    use crate::clock::clock::ManualClock;
+    use crate::errors::errors::TErrorKind;
+    use chrono::{Datelike, Duration, FixedOffset, Local, TimeZone, Timelike, Utc, Weekday};
 
     #[test]
     fn test_parser_next() {
-        let next_date = parser_next("Day 05:00:00").unwrap();
-        let next_tick = parser_timestamp("Day 05:00:00").unwrap();
-
-        let next_weekDAY = parser_next("WEEK 2 06:00:00").unwrap();
-        let next_weekTick = parser_timestamp("WEEK 2 06:00:00").unwrap();
+        let now = Local::now();
+        let day = parser_next("Day 05:00:00").unwrap();
+        assert!(day > now && day <= now + Duration::days(1));
+        assert_eq!((day.hour(),day.minute(),day.second()),(5,0,0));
+        assert_eq!(parser_timestamp("Day 05:00:00").unwrap(),day.timestamp());
+
+        let week = parser_next("WEEK 2 06:00:00").unwrap();
+        assert!(week > now && week <= now + Duration::days(7));
+        assert_eq!(week.weekday(),Weekday::Tue);
+        assert_eq!(parser_timestamp("WEEK 2 06:00:00").unwrap(),week.timestamp());
+
+        let month = parser_next("Month 2 06:00:00").unwrap();
+        assert!(month > now && month <= now + Duration::days(31));
+        assert_eq!(month.day(),2);
+        assert_eq!(parser_timestamp("Month 2 06:00:00").unwrap(),month.timestamp());
+    }
 
-        let next_monthDAY = parser_next("Month 2 06:00:00").unwrap();
-        let next_monthTick = parser_timestamp("Month 2 06:00:00").unwrap();
+    #[test]
+    fn test_parser_month() {
+        // 12月之后是下一年的1月
+        let clock = ManualClock::new(Local.ymd(2026,12,15).and_hms(0,0,0));
+        assert_eq!(parser_next_with("Month 2 06:00:00",&clock).unwrap(),Local.ymd(2027,1,2).and_hms(6,0,0));
+        assert_eq!(parser_next_with("Month 31 06:00:00",&clock).unwrap(),Local.ymd(2026,12,31).and_hms(6,0,0));
+
+        // 没有这一天的月份跳过
+        clock.set(Local.ymd(2026,1,31).and_hms(6,0,0));
+        assert_eq!(parser_next_with("Month 31 05:00:00",&clock).unwrap(),Local.ymd(2026,3,31).and_hms(5,0,0));
+        clock.set(Local.ymd(2027,1,30).and_hms(0,0,0));
+        assert_eq!(parser_next_with("Month 29 05:00:00",&clock).unwrap(),Local.ymd(2027,3,29).and_hms(5,0,0));
+        clock.set(Local.ymd(2026,4,1).and_hms(0,0,0));
+        assert_eq!(parser_next_with("Month 31 05:00:00",&clock).unwrap(),Local.ymd(2026,5,31).and_hms(5,0,0));
+
+        for v in ["Month 0 05:00:00","Month 32 05:00:00"] {
+            assert!(matches!(parser_next_with(v,&clock).map_err(|e| e.kind().clone()),Err(TErrorKind::DateOverflow)),"{}",v);
+        }
 
-        println!("day {} -  unix:{}",next_date,next_tick);
-        println!("week {} -  unix:{}",next_weekDAY,next_weekTick);
-        println!("month {} -  unix:{}",next_monthDAY,next_monthTick);
+        let clock = ManualClock::new(Utc.ymd(2026,12,31).and_hms(7,0,0).with_timezone(&Local));
+        assert_eq!(parser_nextUtc_with("Month 31 06:00:00",&clock).unwrap(),Utc.ymd(2027,1,31).and_hms(6,0,0));
+        clock.set(Utc.ymd(2027,1,31).and_hms(7,0,0).with_timezone(&Local));
+        assert_eq!(parser_nextUtc_with("Month 30 06:00:00",&clock).unwrap(),Utc.ymd(2027,3,30).and_hms(6,0,0));
     }
 
     #[test]
     fn test_parser_utc() {
-        let next_date = parser_nextUtc("Day 05:00:00").unwrap();
-        let next_tick = parser_timestampUtc("Day 05:00:00").unwrap();
+        let now = Utc::now();
+        let day = parser_nextUtc("Day 05:00:00").unwrap();
+        assert!(day > now && day <= now + Duration::days(1));
+        assert_eq!((day.hour(),day.minute(),day.second()),(5,0,0));
+        assert_eq!(parser_timestampUtc("Day 05:00:00").unwrap(),day.timestamp());
+
+        let week = parser_nextUtc("WEEK 2 06:00:00").unwrap();
+        assert!(week > now && week <= now + Duration::days(7));
+        assert_eq!(week.weekday(),Weekday::Tue);
+        assert_eq!(parser_timestampUtc("WEEK 2 06:00:00").unwrap(),week.timestamp());
+
+        let month = parser_nextUtc("Month 2 06:00:00").unwrap();
+        assert!(month > now && month <= now + Duration::days(31));
+        assert_eq!(month.day(),2);
+        assert_eq!(parser_timestampUtc("Month 2 06:00:00").unwrap(),month.timestamp());
+    }
 
-        let next_weekDAY = parser_nextUtc("WEEK 2 06:00:00").unwrap();
-        let next_weekTick = parser_timestampUtc("WEEK 2 06:00:00").unwrap();
+    #[test]
+    fn test_parser_aligned() {
+        let every = Aligned::new(Duration::minutes(5),Duration::zero(),AlignAnchor::Epoch).unwrap();
+        let now = Utc.ymd(2024,3,1).and_hms(10,7,12);
+        assert_eq!(every.next_after(&now),Utc.ymd(2024,3,1).and_hms(10,10,0));
+        // 正好在边界上时取下一个
+        assert_eq!(every.next_after(&Utc.ymd(2024,3,1).and_hms(10,10,0)),Utc.ymd(2024,3,1).and_hms(10,15,0));
+
+        // 所在时区的零点，+05:30的整点
+        let hourly = Aligned::new(Duration::hours(1),Duration::minutes(15),AlignAnchor::Midnight).unwrap();
+        let tz = FixedOffset::east(5 * 3600 + 1800);
+        let next = hourly.next_after(&tz.ymd(2024,3,1).and_hms(23,20,0));
+        assert_eq!(next,tz.ymd(2024,3,2).and_hms(0,15,0));
+        assert_eq!(next.minute(),15);
+
+        // 指定时区的零点，与计算使用的时区无关
+        let seven = Aligned::new(Duration::hours(7),Duration::zero(),AlignAnchor::MidnightAt(FixedOffset::east(8 * 3600))).unwrap();
+        let next = seven.next_after(&Utc.ymd(2024,3,1).and_hms(14,0,0));
+        assert_eq!(next,Utc.ymd(2024,3,1).and_hms(16,0,0));
+        let utc = Aligned::new(Duration::hours(7),Duration::zero(),AlignAnchor::MidnightAt(FixedOffset::east(0))).unwrap();
+        assert_eq!(utc.next_after(&tz.ymd(2024,3,1).and_hms(12,0,0)),tz.ymd(2024,3,1).and_hms(12,30,0));
+        let local = Aligned::new(Duration::hours(7),Duration::zero(),AlignAnchor::LocalMidnight).unwrap();
+        let now = Local.ymd(2024,3,1).and_hms(13,0,0);
+        assert_eq!(local.next_after(&now.with_timezone(&Utc)),Local.ymd(2024,3,1).and_hms(14,0,0).with_timezone(&Utc));
+
+        // 不能整除一天的间隔，从零点重新开始
+        let odd = Aligned::new(Duration::minutes(7),Duration::zero(),AlignAnchor::Midnight).unwrap();
+        assert_eq!(odd.next_after(&Utc.ymd(2024,3,1).and_hms(23,58,0)),Utc.ymd(2024,3,2).and_hms(0,0,0));
+
+        let next = parser_nextUtc("Every 5m").unwrap();
+        assert_eq!(next.second(),0);
+        assert_eq!(next.minute() % 5,0);
+        assert!(next > Utc::now() && next <= Utc::now() + Duration::minutes(5));
+        assert_eq!(parser_next("Every 30s").unwrap().second() % 30,0);
+    }
 
-        let next_monthDAY = parser_nextUtc("Month 2 06:00:00").unwrap();
-        let next_monthTick = parser_timestampUtc("Month 2 06:00:00").unwrap();
+    #[test]
+    fn test_aligned_dst() {
+        use chrono_tz::America::{New_York, Sao_Paulo};
+        let mut datep = DateParser::new();
+        datep.parser("Every 1h Midnight").unwrap();
+        let hourly = datep.aligned.unwrap();
+
+        // 冬令时开始的一天有25小时，第二天的零点仍在现在之后
+        let now = New_York.ymd(2026,11,1).and_hms(23,30,0);
+        assert_eq!(hourly.next_after(&now),New_York.ymd(2026,11,2).and_hms(0,0,0));
+        // 重复的一小时按实际经过的时间对齐
+        let repeated = New_York.ymd(2026,11,1).and_hms(0,30,0) + Duration::hours(1);
+        assert_eq!(hourly.next_after(&repeated),New_York.ymd(2026,11,1).and_hms(0,0,0) + Duration::hours(2));
+
+        // 夏令时开始的一天有23小时，02:00不存在
+        assert_eq!(hourly.next_after(&New_York.ymd(2026,3,8).and_hms(1,30,0)),New_York.ymd(2026,3,8).and_hms(3,0,0));
+        let five = Aligned::new(Duration::hours(5),Duration::zero(),AlignAnchor::Midnight).unwrap();
+        assert_eq!(five.next_after(&New_York.ymd(2026,3,8).and_hms(22,0,0)),New_York.ymd(2026,3,9).and_hms(0,0,0));
+
+        // 夏令时从零点开始时，这一天从01:00开始
+        assert_eq!(hourly.next_after(&Sao_Paulo.ymd(2018,11,3).and_hms(23,30,0)),Sao_Paulo.ymd(2018,11,4).and_hms(1,0,0));
+        assert_eq!(hourly.next_after(&Sao_Paulo.ymd(2018,11,4).and_hms(10,20,0)),Sao_Paulo.ymd(2018,11,4).and_hms(11,0,0));
+        assert_eq!(five.next_after(&Sao_Paulo.ymd(2018,11,4).and_hms(1,20,0)),Sao_Paulo.ymd(2018,11,4).and_hms(6,0,0));
+    }
 
-        println!("utc day {} -  unix:{}",next_date,next_tick);
-        println!("utc week {} -  unix:{}",next_weekDAY,next_weekTick);
-        println!("utc month {} -  unix:{}",next_monthDAY,next_monthTick);
+    #[test]
+    fn test_parser_clock() {
+        // 2026-03-02是周一
+        let clock = ManualClock::new(Local.ymd(2026,3,2).and_hms(4,30,0));
+        assert_eq!(parser_next_with("Day 05:00:00",&clock).unwrap(),Local.ymd(2026,3,2).and_hms(5,0,0));
+        assert_eq!(parser_next_with("Week 3 06:00:00",&clock).unwrap(),Local.ymd(2026,3,4).and_hms(6,0,0));
+        assert_eq!(parser_next_with("Month 2 06:00:00",&clock).unwrap(),Local.ymd(2026,3,2).and_hms(6,0,0));
+        assert_eq!(parser_next_with("Every 15m",&clock).unwrap(),Local.ymd(2026,3,2).and_hms(4,45,0));
+
+        clock.advance(std::time::Duration::from_secs(3600));
+        assert_eq!(parser_next_with("Day 05:00:00",&clock).unwrap(),Local.ymd(2026,3,3).and_hms(5,0,0));
+        assert_eq!(parser_next_with("Month 2 06:00:00",&clock).unwrap(),Local.ymd(2026,3,2).and_hms(6,0,0));
+        // 当天的时间已经过了，等到下周
+        assert_eq!(parser_next_with("Week 1 05:00:00",&clock).unwrap(),Local.ymd(2026,3,9).and_hms(5,0,0));
+
+        let clock = ManualClock::new(Utc.ymd(2026,3,2).and_hms(4,30,0).with_timezone(&Local));
+        assert_eq!(parser_nextUtc_with("Day 05:00:00",&clock).unwrap(),Utc.ymd(2026,3,2).and_hms(5,0,0));
+        clock.set(Utc.ymd(2026,3,2).and_hms(5,0,0).with_timezone(&Local));
+        assert_eq!(parser_nextUtc_with("Day 05:00:00",&clock).unwrap(),Utc.ymd(2026,3,3).and_hms(5,0,0));
+        assert_eq!(parser_nextUtc_with("Week 1 05:00:00",&clock).unwrap(),Utc.ymd(2026,3,9).and_hms(5,0,0));
     }
-}
\ No newline at end of file
+}
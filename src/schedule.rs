@@ -1,56 +1,479 @@
+#[allow(clippy::module_inception)]
 pub mod schedule {
     use std::time;
     use std::time::Duration;
-    use std::ops::FnMut;
-    use std::borrow::{Borrow, BorrowMut};
-    use crate::errors::errors::TError;
-    use std::sync::Arc;
-    use std::sync::atomic::{AtomicI64, AtomicI32};
+    use std::sync::{Arc,Mutex,mpsc};
+    use std::fmt;
+    use std::panic::AssertUnwindSafe;
+    use std::future::Future;
+    use std::pin::Pin;
+    use crate::errors::errors::{TError, TErrorKind, TResult};
+    use crate::parsers::parsers::parser_next;
+    use rand::Rng;
+    use tokio::time::Instant;
+    use tokio::sync::oneshot;
+    use tokio::task::JoinHandle;
+    use chrono::{DateTime, Local};
+    use crate::context::context::ExecContext;
 
-    pub trait TaskAction : Send + Sync {
-        fn execute(&self,id:u64) {}
-        fn date_format(&self) -> &str;
-        fn tick(&self) -> u64;
-        fn id(&self) -> u64;
-        fn loop_count(&self) -> i32;
+    /// when a task fires [任务的执行计划，间隔或日期格式]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::schedule::schedule::Schedule;
+    /// use std::time;
+    ///
+    /// let tick = Schedule::from(time::Duration::from_secs(5));
+    /// let date = Schedule::from("Week 1 05:00:00");
+    /// assert!(tick.validate().is_ok() && date.validate().is_ok());
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Schedule {
+        Tick(Duration),  // 固定间隔
+        Date(String),    // 日期格式，如 Day 05:00:00
     }
 
-    struct TaskEmpty {}
-    impl TaskAction for TaskEmpty {
-        fn date_format(&self) -> &str {
-            ""
+    impl Schedule {
+        /// schedule of a trait action [从TaskAction中获取执行计划]
+        pub fn from_action(t:&dyn TaskAction) -> Schedule {
+            if t.date_format().is_empty() {
+                Schedule::Tick(Duration::from_millis(t.tick()))
+            }else {
+                Schedule::Date(String::from(t.date_format()))
+            }
         }
 
-        fn tick(&self) -> u64 {
-            0
+        /// check the tick or date format [检查间隔或日期格式是否有效]
+        pub fn validate(&self) -> TResult<()> {
+            match self {
+                Schedule::Tick(d) => {
+                    if d.as_millis() == 0 {
+                        return Err(TError::new(TErrorKind::AllocTickerError));
+                    }
+                    Ok(())
+                }
+                Schedule::Date(v) => {
+                    parser_next(v)?;
+                    Ok(())
+                }
+            }
         }
 
-        fn id(&self) -> u64 {
-            0
+        /// interval aligned to wall-clock boundaries from local midnight [从本地零点开始按时钟边界对齐的间隔]
+        ///
+        /// same as the date format `Every 5m +30s`
+        pub fn aligned(period:Duration,offset:Duration) -> Schedule {
+            Schedule::Date(format!("Every {}ms +{}ms",period.as_millis(),offset.as_millis()))
+        }
+
+        pub fn is_tick(&self) -> bool {
+            matches!(self,Schedule::Tick(_))
+        }
+    }
+
+    impl From<Duration> for Schedule {
+        fn from(d:Duration) -> Self {
+            Schedule::Tick(d)
+        }
+    }
+
+    impl From<&str> for Schedule {
+        fn from(v:&str) -> Self {
+            Schedule::Date(String::from(v))
+        }
+    }
+
+    impl fmt::Display for Schedule {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Schedule::Tick(d) => { write!(f,"Tick {}ms",d.as_millis()) }
+                Schedule::Date(v) => { write!(f,"{}",v) }
+            }
+        }
+    }
+
+    /// how many times or until when a task runs [任务执行的次数或截止时间]
+    ///
+    /// An `i32` converts for compatibility, `<= 0` is `Forever`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::schedule::schedule::RunLimit;
+    /// use chrono::{Local, Duration};
+    ///
+    /// assert_eq!(RunLimit::from(0),RunLimit::Forever);
+    /// assert_eq!(RunLimit::from(3).runs_left(1),Some(2));
+    /// let limit = RunLimit::TimesOrUntil(10,Local::now() + Duration::days(7));
+    /// assert!(limit.until().is_some());
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum RunLimit {
+        #[default]
+        Forever,                            // 一直执行
+        Times(u64),                         // 执行N次
+        Until(DateTime<Local>),             // 执行到指定时间
+        TimesOrUntil(u64,DateTime<Local>),  // 执行N次或到指定时间，先到为准
+    }
+
+    impl RunLimit {
+        /// max number of runs [最多执行的次数]
+        pub fn times(&self) -> Option<u64> {
+            match self {
+                RunLimit::Times(n) | RunLimit::TimesOrUntil(n,_) => { Some(*n) }
+                _ => { None }
+            }
+        }
+
+        /// end time [截止时间]
+        pub fn until(&self) -> Option<DateTime<Local>> {
+            match self {
+                RunLimit::Until(v) | RunLimit::TimesOrUntil(_,v) => { Some(*v) }
+                _ => { None }
+            }
+        }
+
+        /// how many runs are left after run_count runs, `None` if not limited by count [剩余的执行次数，不限次数时为None]
+        pub fn runs_left(&self,run_count:u64) -> Option<u64> {
+            self.times().map(|n| n.saturating_sub(run_count))
         }
 
-        fn loop_count(&self) -> i32 {
-            0
+        // 次数是否已经用完
+        pub(crate) fn is_exhausted(&self,run_count:u64) -> bool {
+            self.runs_left(run_count) == Some(0)
         }
     }
 
+    impl From<i32> for RunLimit {
+        fn from(v:i32) -> Self {
+            if v <= 0 {
+                RunLimit::Forever
+            }else {
+                RunLimit::Times(v as u64)
+            }
+        }
+    }
+
+    /// what to do when an execution panics or fails [任务执行失败时的处理策略]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum FailurePolicy {
+        #[default]
+        Continue,      // 继续执行
+        Stop,          // 立即停止
+        StopAfter(u32), // 连续失败N次后停止
+    }
+
+    impl FailurePolicy {
+        // 连续失败的次数是否需要停止任务
+        pub fn should_stop(&self,consecutive:u32) -> bool {
+            match self {
+                FailurePolicy::Continue => { false }
+                FailurePolicy::Stop => { true }
+                FailurePolicy::StopAfter(n) => { consecutive >= *n }
+            }
+        }
+    }
+
+    /// what to do when a tick comes while an execution is still running [执行时间超过间隔时的处理策略]
+    ///
+    /// `Serial` waits for the execution before sleeping again, the other policies
+    /// drive ticks on their own schedule and run each execution in a separate future.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum OverlapPolicy {
+        #[default]
+        Serial,          // 执行结束后才开始下一次等待
+        SkipIfRunning,   // 仍在执行时跳过这一次
+        Queue(usize),    // 排队等待，最多N个，超出的跳过
+        Concurrent(usize), // 最多同时执行N个，超出的跳过
+    }
+
+    /// where a sync task body runs [同步任务体的执行位置]
+    ///
+    /// A CPU-heavy or file I/O body should not run `Inline`, it holds a worker thread and delays
+    /// every other task on it. Async bodies always run on the worker threads.
+    ///
+    /// An `Inline` body with a timeout runs on the blocking pool instead, so the timeout can fire.
+    /// A sync body cannot be interrupted, on timeout its token is cancelled and the body is left
+    /// to return by itself while the schedule moves on, so it should check the token.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum ExecMode {
+        #[default]
+        Inline,          // 在工作线程中执行，设置了超时时在阻塞线程池中执行
+        Blocking,        // 在阻塞线程池中执行，线程数由Config中的blocking_thread_count限制
+        DedicatedThread, // 在任务独占的线程中依次执行
+    }
+
+    /// how a ticker computes its next tick [间隔任务计算下一次执行的方式]
+    ///
+    /// `FixedDelay` sleeps a full tick after each run, so execution time adds up as drift.
+    /// `FixedRate` anchors ticks to the start instant like `tokio::time::interval`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum TickMode {
+        #[default]
+        FixedDelay,                  // 每次执行后等待一个完整的间隔
+        FixedRate(MissedTickBehavior), // 以启动时间为锚点，不累积误差
+    }
+
+    /// what a fixed rate ticker does when it falls behind [固定频率的任务落后时的处理方式]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum MissedTickBehavior {
+        #[default]
+        Burst, // 立即补上错过的执行
+        Delay, // 从现在开始重新计算间隔
+        Skip,  // 跳过错过的执行，保持原来的节奏
+    }
+
+    impl MissedTickBehavior {
+        // 上一次应执行的时间为last，计算下一次执行的时间
+        pub(crate) fn next_due(&self,last:Instant,period:Duration,now:Instant) -> Instant {
+            let next = last + period;
+            if next > now {
+                return next;
+            }
+            match self {
+                MissedTickBehavior::Burst => { next }
+                MissedTickBehavior::Delay => { now + period }
+                MissedTickBehavior::Skip => {
+                    let behind = (now - last).as_nanos() / period.as_nanos();
+                    last + period * (behind as u32 + 1)
+                }
+            }
+        }
+    }
+
+    /// how long to wait between retries [重试之间的等待方式]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Backoff {
+        Fixed(Duration),                              // 固定间隔
+        Linear(Duration),                             // 每次增加相同的时间
+        Exponential { base:Duration, max:Duration },  // 每次翻倍，不超过max
+    }
+
+    /// re-run a failed execution before the next regular slot [失败后在下一次正常执行前重试]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::schedule::schedule::{RetryPolicy, Backoff};
+    /// use std::time::Duration;
+    ///
+    /// let retry = RetryPolicy{
+    ///         max_attempts: 3,
+    ///         backoff: Backoff::Exponential{ base: Duration::from_secs(60), max: Duration::from_secs(600) },
+    ///         jitter: Duration::from_secs(10),
+    ///     };
+    /// assert_eq!(retry.delay(3),Duration::from_secs(240));
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RetryPolicy {
+        pub max_attempts:u32,  // 最多重试的次数，不包括第一次执行
+        pub backoff:Backoff,
+        pub jitter:Duration,   // 每次重试随机增加 0~jitter 的等待
+    }
+
+    impl RetryPolicy {
+        /// delay before the attempt-th retry without jitter, attempt starts at 1 [第attempt次重试前的等待时间，不含随机部分]
+        pub fn delay(&self,attempt:u32) -> Duration {
+            let attempt = attempt.max(1);
+            match self.backoff {
+                Backoff::Fixed(d) => { d }
+                Backoff::Linear(d) => { d.saturating_mul(attempt) }
+                Backoff::Exponential{ base, max } => {
+                    let factor = 2u32.checked_pow(attempt - 1).unwrap_or(u32::MAX);
+                    base.saturating_mul(factor).min(max)
+                }
+            }
+        }
+
+        // 加上随机部分的等待时间
+        pub fn delay_with_jitter(&self,attempt:u32) -> Duration {
+            let delay = self.delay(attempt);
+            if self.jitter.is_zero() {
+                return delay;
+            }
+            let extra = rand::thread_rng().gen_range(0..=self.jitter.as_millis() as u64);
+            delay + Duration::from_millis(extra)
+        }
+    }
+
+    /// extra delay added before each run to spread tasks with the same schedule [每次执行前额外的等待，分散相同计划的任务]
+    ///
+    /// `Percent` is a percent of the tick for tickers, and of the wait until the next date for date tasks.
+    /// `Spread` hashes the key to a fixed offset inside the window, stable from run to run. A ticker
+    /// is shifted by it once, on its first wait, and keeps its tick afterwards.
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub enum Jitter {
+        #[default]
+        None,
+        Range(Duration),                       // 0~Duration之间的随机时间
+        Percent(u32),                          // 间隔的百分比内的随机时间
+        Spread { key:String, window:Duration }, // 按key固定分散在窗口内
+    }
+
+    impl Jitter {
+        // 这一次执行额外等待的时间，base为间隔或到下一次日期的等待时间
+        pub(crate) fn delay(&self,base:Duration) -> Duration {
+            self.spread() + self.random(base)
+        }
+
+        // 按key固定的偏移，其他方式为0
+        pub(crate) fn spread(&self) -> Duration {
+            match self {
+                Jitter::Spread { key, window } => {
+                    let window = window.as_millis() as u64;
+                    if window == 0 {
+                        return Duration::from_millis(0);
+                    }
+                    Duration::from_millis(spread_hash(key) % window)
+                }
+                _ => { Duration::from_millis(0) }
+            }
+        }
+
+        // 每次重新随机的等待，固定分散为0
+        pub(crate) fn random(&self,base:Duration) -> Duration {
+            let range = match self {
+                Jitter::Range(v) => { v.as_millis() as u64 }
+                Jitter::Percent(pct) => { (base.as_millis() as u64).saturating_mul(*pct as u64) / 100 }
+                _ => { 0 }
+            };
+            if range == 0 {
+                return Duration::from_millis(0);
+            }
+            Duration::from_millis(rand::thread_rng().gen_range(0..=range))
+        }
+    }
+
+    // FNV-1a，不同进程和版本之间结果一致
+    fn spread_hash(key:&str) -> u64 {
+        key.bytes().fold(0xcbf29ce484222325,|hash,v| (hash ^ v as u64).wrapping_mul(0x100000001b3))
+    }
+
+    /// per task options [任务的可选配置]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::schedule::schedule::TaskOptions;
+    ///
+    /// let opts = TaskOptions{
+    ///         tags: vec![String::from("player"),String::from("buff")],
+    ///         ..TaskOptions::default()
+    ///     };
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct TaskOptions {
+        pub tags:Vec<String>, // 任务所属的分组标签
+        pub failure_policy:FailurePolicy, // 执行失败时的处理策略
+        pub retry:Option<RetryPolicy>, // 执行失败时的重试策略
+        pub timeout:Option<Duration>, // 单次执行的超时时间，None使用Config中的默认值
+        pub overlap:OverlapPolicy, // 执行时间超过间隔时的处理策略
+        pub tick_mode:TickMode, // 间隔任务计算下一次执行的方式
+        pub jitter:Jitter, // 每次执行前额外的等待，分散相同计划的任务
+        pub start_at:Option<DateTime<Local>>, // 开始时间，间隔任务在开始时执行第一次
+        pub initial_delay:Option<Duration>, // 开始前的等待，与start_at同时设置时取较晚的一个
+        pub end_at:Option<DateTime<Local>>, // 结束时间，下一次执行超过结束时间时任务结束
+        pub exec_mode:ExecMode, // 同步任务体的执行位置，异步任务忽略
+        pub run_at_start:bool, // 在开始时执行一次，没有开始时间或初始延迟时立即执行，日期任务之后按日期执行
+    }
+
+    pub trait TaskAction : Send + Sync {
+        fn execute(&self,_id:u64) {}
+        // 可失败的执行，返回错误时按重试和失败策略处理，默认调用execute
+        fn try_execute(&self,id:u64) -> TResult<()> {
+            self.execute(id);
+            Ok(())
+        }
+        // 带执行上下文的执行，调度器调用这个方法，默认调用try_execute
+        fn execute_ctx(&self,ctx:&ExecContext) -> TResult<()> {
+            self.try_execute(ctx.id)
+        }
+        fn date_format(&self) -> &str;
+        fn tick(&self) -> u64;
+        fn id(&self) -> u64;
+        fn run_limit(&self) -> RunLimit;
+        // 任务所属的分组标签，可以按标签停止或暂停一组任务
+        fn tags(&self) -> Vec<String> { Vec::new() }
+        // 执行失败时的处理策略，默认继续执行
+        fn failure_policy(&self) -> FailurePolicy { FailurePolicy::Continue }
+        // 执行失败时的重试策略，默认不重试
+        fn retry_policy(&self) -> Option<RetryPolicy> { None }
+        // 单次执行的超时时间，默认使用Config中的default_timeout
+        fn timeout(&self) -> Option<Duration> { None }
+        // 执行时间超过间隔时的处理策略，默认串行执行
+        fn overlap_policy(&self) -> OverlapPolicy { OverlapPolicy::Serial }
+        // 间隔任务计算下一次执行的方式，默认固定延迟
+        fn tick_mode(&self) -> TickMode { TickMode::FixedDelay }
+        // 每次执行前额外的等待，默认不等待
+        fn jitter(&self) -> Jitter { Jitter::None }
+        // 开始时间、初始延迟以及结束时间，默认立即开始并且不结束
+        fn start_at(&self) -> Option<DateTime<Local>> { None }
+        fn initial_delay(&self) -> Option<Duration> { None }
+        fn end_at(&self) -> Option<DateTime<Local>> { None }
+        // 日期任务是否在开始时执行一次，默认等到下一个日期
+        fn run_at_start(&self) -> bool { false }
+        // 任务体的执行位置，默认在工作线程中执行
+        fn exec_mode(&self) -> ExecMode { ExecMode::Inline }
+    }
+
+    // 闭包任务实际调用的函数
+    type ContextFn = Arc<dyn Fn(&ExecContext) -> TResult<()> + Send + Sync + 'static>;
+
     // 内部实现的自己绑定自己函数的实现
     pub struct ClosuresAction {
         date_format: String,
         id:u64,
-        loop_count:i32,
+        run_limit:RunLimit,
         tick:Duration,
-        call:Arc<dyn Fn(u64) + Send + Sync + 'static>,
+        options:TaskOptions,
+        call:ContextFn,
     }
 
     impl ClosuresAction {
-        pub fn new(date:&str,idx:u64,loopC:i32,t:time::Duration,f: impl Fn(u64) + Send+Sync + 'static) -> ClosuresAction {
+        pub fn new(date:&str,idx:u64,limit:impl Into<RunLimit>,t:time::Duration,f: impl Fn(u64) + Send+Sync + 'static) -> ClosuresAction {
             ClosuresAction {
                 date_format: String::from(date),
                 id: idx,
                 tick:t,
-                loop_count: loopC,
-                call:Arc::new(f),
+                run_limit: limit.into(),
+                options:TaskOptions::default(),
+                call:Arc::new(move |ctx| { f(ctx.id); Ok(()) }),
+            }
+        }
+
+        // 可变的闭包，状态由任务自己持有；串行执行下锁不会被争用
+        pub fn new_mut(date:&str,idx:u64,limit:impl Into<RunLimit>,t:time::Duration,f: impl FnMut(u64) + Send + 'static) -> ClosuresAction {
+            let f = Mutex::new(f);
+            ClosuresAction::new(date,idx,limit,t,move |id| {
+                // 上一次执行panic后继续使用原来的状态
+                let mut f = f.lock().unwrap_or_else(|e| e.into_inner());
+                (*f)(id)
+            })
+        }
+
+        pub fn with_options(schedule:Schedule,idx:u64,limit:impl Into<RunLimit>,opts:TaskOptions,f: impl Fn(u64) + Send+Sync + 'static) -> ClosuresAction {
+            ClosuresAction::fallible(schedule,idx,limit,opts,move |id| -> Result<(),TError> { f(id); Ok(()) })
+        }
+
+        pub fn fallible<E:fmt::Display>(schedule:Schedule,idx:u64,limit:impl Into<RunLimit>,opts:TaskOptions,f: impl Fn(u64) -> Result<(),E> + Send+Sync + 'static) -> ClosuresAction {
+            ClosuresAction::with_context(schedule,idx,limit,opts,move |ctx| f(ctx.id))
+        }
+
+        pub fn with_context<E:fmt::Display>(schedule:Schedule,idx:u64,limit:impl Into<RunLimit>,opts:TaskOptions,f: impl Fn(&ExecContext) -> Result<(),E> + Send+Sync + 'static) -> ClosuresAction {
+            let (date,tick) = match schedule {
+                Schedule::Tick(d) => { (String::new(),d) }
+                Schedule::Date(v) => { (v,Duration::from_secs(0)) }
+            };
+            ClosuresAction {
+                date_format: date,
+                id: idx,
+                tick,
+                run_limit: limit.into(),
+                options:opts,
+                call:Arc::new(move |ctx| {
+                    f(ctx).map_err(|e| TError::new(TErrorKind::Other(e.to_string())))
+                }),
             }
         }
     }
@@ -58,7 +481,15 @@ pub mod schedule {
     // 实现这个trait
     impl TaskAction for ClosuresAction {
         fn execute(&self,id:u64) {
-            (self.call)(id);
+            let _ = self.try_execute(id);
+        }
+
+        fn try_execute(&self,id:u64) -> TResult<()> {
+            (self.call)(&ExecContext::detached(id))
+        }
+
+        fn execute_ctx(&self,ctx:&ExecContext) -> TResult<()> {
+            (self.call)(ctx)
         }
 
         fn date_format(&self) -> &str {
@@ -68,8 +499,390 @@ pub mod schedule {
         fn id(&self) -> u64 {
             self.id
         }
-        fn loop_count(&self) -> i32 {
-            self.loop_count
+        fn run_limit(&self) -> RunLimit {
+            self.run_limit
+        }
+        fn tags(&self) -> Vec<String> {
+            self.options.tags.clone()
+        }
+        fn failure_policy(&self) -> FailurePolicy {
+            self.options.failure_policy
+        }
+        fn retry_policy(&self) -> Option<RetryPolicy> {
+            self.options.retry
+        }
+        fn timeout(&self) -> Option<Duration> {
+            self.options.timeout
+        }
+        fn overlap_policy(&self) -> OverlapPolicy {
+            self.options.overlap
+        }
+        fn tick_mode(&self) -> TickMode {
+            self.options.tick_mode
+        }
+        fn jitter(&self) -> Jitter {
+            self.options.jitter.clone()
+        }
+        fn start_at(&self) -> Option<DateTime<Local>> {
+            self.options.start_at
+        }
+        fn initial_delay(&self) -> Option<Duration> {
+            self.options.initial_delay
+        }
+        fn end_at(&self) -> Option<DateTime<Local>> {
+            self.options.end_at
+        }
+        fn run_at_start(&self) -> bool {
+            self.options.run_at_start
+        }
+        fn exec_mode(&self) -> ExecMode {
+            self.options.exec_mode
+        }
+    }
+
+    /// future returned by an async task [异步任务返回的Future]
+    pub type TaskFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+    /// future returned by a fallible async task [可失败的异步任务返回的Future]
+    pub type TryTaskFuture = Pin<Box<dyn Future<Output = TResult<()>> + Send + 'static>>;
+
+    /// async counterpart of TaskAction [TaskAction的异步版本，execute返回一个Future]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::schedule::schedule::{AsyncTaskAction, TaskFuture, RunLimit};
+    ///
+    /// struct SaveAction {}
+    ///
+    /// impl AsyncTaskAction for SaveAction {
+    ///     fn execute(&self, id: u64) -> TaskFuture {
+    ///         Box::pin(async move {
+    ///             println!("save to db:{}",id);
+    ///         })
+    ///     }
+    ///
+    ///     fn date_format(&self) -> &str { "" }
+    ///     fn tick(&self) -> u64 { 5000 }
+    ///     fn id(&self) -> u64 { 28888 }
+    ///     fn run_limit(&self) -> RunLimit { RunLimit::Times(15) }
+    /// }
+    /// ```
+    pub trait AsyncTaskAction : Send + Sync {
+        fn execute(&self,id:u64) -> TaskFuture;
+        // 可失败的执行，返回错误时按重试和失败策略处理，默认调用execute
+        fn try_execute(&self,id:u64) -> TryTaskFuture {
+            let fut = self.execute(id);
+            Box::pin(async move {
+                fut.await;
+                Ok(())
+            })
+        }
+        // 带执行上下文的执行，调度器调用这个方法，默认调用try_execute
+        fn execute_ctx(&self,ctx:ExecContext) -> TryTaskFuture {
+            self.try_execute(ctx.id)
+        }
+        fn date_format(&self) -> &str;
+        fn tick(&self) -> u64;
+        fn id(&self) -> u64;
+        fn run_limit(&self) -> RunLimit;
+        // 任务所属的分组标签，可以按标签停止或暂停一组任务
+        fn tags(&self) -> Vec<String> { Vec::new() }
+        // 执行失败时的处理策略，默认继续执行
+        fn failure_policy(&self) -> FailurePolicy { FailurePolicy::Continue }
+        // 执行失败时的重试策略，默认不重试
+        fn retry_policy(&self) -> Option<RetryPolicy> { None }
+        // 单次执行的超时时间，默认使用Config中的default_timeout
+        fn timeout(&self) -> Option<Duration> { None }
+        // 执行时间超过间隔时的处理策略，默认串行执行
+        fn overlap_policy(&self) -> OverlapPolicy { OverlapPolicy::Serial }
+        // 间隔任务计算下一次执行的方式，默认固定延迟
+        fn tick_mode(&self) -> TickMode { TickMode::FixedDelay }
+        // 每次执行前额外的等待，默认不等待
+        fn jitter(&self) -> Jitter { Jitter::None }
+        // 开始时间、初始延迟以及结束时间，默认立即开始并且不结束
+        fn start_at(&self) -> Option<DateTime<Local>> { None }
+        fn initial_delay(&self) -> Option<Duration> { None }
+        fn end_at(&self) -> Option<DateTime<Local>> { None }
+        // 日期任务是否在开始时执行一次，默认等到下一个日期
+        fn run_at_start(&self) -> bool { false }
+    }
+
+    // 异步闭包的实现
+    pub struct AsyncClosuresAction {
+        schedule:Schedule,
+        id:u64,
+        run_limit:RunLimit,
+        options:TaskOptions,
+        call:Arc<dyn Fn(ExecContext) -> TryTaskFuture + Send + Sync + 'static>,
+    }
+
+    impl AsyncClosuresAction {
+        pub fn new<F,Fut>(schedule:Schedule,idx:u64,limit:impl Into<RunLimit>,opts:TaskOptions,f:F) -> AsyncClosuresAction
+            where
+                F: Fn(u64) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = ()> + Send + 'static,
+        {
+            AsyncClosuresAction::fallible(schedule,idx,limit,opts,move |id| {
+                let fut = f(id);
+                async move {
+                    fut.await;
+                    Ok::<(),TError>(())
+                }
+            })
+        }
+
+        pub fn fallible<F,Fut,E>(schedule:Schedule,idx:u64,limit:impl Into<RunLimit>,opts:TaskOptions,f:F) -> AsyncClosuresAction
+            where
+                F: Fn(u64) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = Result<(),E>> + Send + 'static,
+                E: fmt::Display,
+        {
+            AsyncClosuresAction::with_context(schedule,idx,limit,opts,move |ctx:ExecContext| f(ctx.id))
+        }
+
+        pub fn with_context<F,Fut,E>(schedule:Schedule,idx:u64,limit:impl Into<RunLimit>,opts:TaskOptions,f:F) -> AsyncClosuresAction
+            where
+                F: Fn(ExecContext) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = Result<(),E>> + Send + 'static,
+                E: fmt::Display,
+        {
+            AsyncClosuresAction {
+                schedule,
+                id: idx,
+                run_limit: limit.into(),
+                options:opts,
+                call:Arc::new(move |ctx| -> TryTaskFuture {
+                    let fut = f(ctx);
+                    Box::pin(async move {
+                        fut.await.map_err(|e| TError::new(TErrorKind::Other(e.to_string())))
+                    })
+                }),
+            }
+        }
+    }
+
+    impl AsyncTaskAction for AsyncClosuresAction {
+        fn execute(&self,id:u64) -> TaskFuture {
+            let fut = self.try_execute(id);
+            Box::pin(async move {
+                let _ = fut.await;
+            })
+        }
+
+        fn try_execute(&self,id:u64) -> TryTaskFuture {
+            (self.call)(ExecContext::detached(id))
+        }
+
+        fn execute_ctx(&self,ctx:ExecContext) -> TryTaskFuture {
+            (self.call)(ctx)
+        }
+
+        fn date_format(&self) -> &str {
+            match &self.schedule {
+                Schedule::Date(v) => { v.as_str() }
+                Schedule::Tick(_) => { "" }
+            }
+        }
+        fn tick(&self) -> u64 {
+            match &self.schedule {
+                Schedule::Tick(d) => { d.as_millis() as u64 }
+                Schedule::Date(_) => { 0 }
+            }
+        }
+        fn id(&self) -> u64 {
+            self.id
+        }
+        fn run_limit(&self) -> RunLimit {
+            self.run_limit
+        }
+        fn tags(&self) -> Vec<String> {
+            self.options.tags.clone()
+        }
+        fn failure_policy(&self) -> FailurePolicy {
+            self.options.failure_policy
+        }
+        fn retry_policy(&self) -> Option<RetryPolicy> {
+            self.options.retry
+        }
+        fn timeout(&self) -> Option<Duration> {
+            self.options.timeout
+        }
+        fn overlap_policy(&self) -> OverlapPolicy {
+            self.options.overlap
+        }
+        fn tick_mode(&self) -> TickMode {
+            self.options.tick_mode
+        }
+        fn jitter(&self) -> Jitter {
+            self.options.jitter.clone()
+        }
+        fn start_at(&self) -> Option<DateTime<Local>> {
+            self.options.start_at
+        }
+        fn initial_delay(&self) -> Option<Duration> {
+            self.options.initial_delay
+        }
+        fn end_at(&self) -> Option<DateTime<Local>> {
+            self.options.end_at
+        }
+        fn run_at_start(&self) -> bool {
+            self.options.run_at_start
+        }
+    }
+
+    // 调度器内部使用的任务定义
+    pub(crate) struct TaskDef {
+        pub id:u64,
+        pub schedule:Schedule,
+        pub run_limit:RunLimit,
+        pub options:TaskOptions,
+    }
+
+    impl TaskDef {
+        pub fn from_action(t:&dyn TaskAction) -> TaskDef {
+            TaskDef {
+                id:t.id(),
+                schedule:Schedule::from_action(t),
+                run_limit:t.run_limit(),
+                options:TaskOptions{ tags:t.tags(), failure_policy:t.failure_policy(), retry:t.retry_policy(), timeout:t.timeout(), overlap:t.overlap_policy(), tick_mode:t.tick_mode(), jitter:t.jitter(),
+                    start_at:t.start_at(), initial_delay:t.initial_delay(), end_at:t.end_at(), exec_mode:t.exec_mode(), run_at_start:t.run_at_start() },
+            }
+        }
+
+        pub fn from_async_action(t:&dyn AsyncTaskAction) -> TaskDef {
+            let schedule = if t.date_format().is_empty() {
+                Schedule::Tick(Duration::from_millis(t.tick()))
+            }else {
+                Schedule::Date(String::from(t.date_format()))
+            };
+            TaskDef {
+                id:t.id(),
+                schedule,
+                run_limit:t.run_limit(),
+                options:TaskOptions{ tags:t.tags(), failure_policy:t.failure_policy(), retry:t.retry_policy(), timeout:t.timeout(), overlap:t.overlap_policy(), tick_mode:t.tick_mode(), jitter:t.jitter(),
+                    start_at:t.start_at(), initial_delay:t.initial_delay(), end_at:t.end_at(), exec_mode:ExecMode::Inline, run_at_start:t.run_at_start() },
+            }
+        }
+    }
+
+    // 调度器内部使用的任务执行体
+    #[derive(Clone)]
+    pub(crate) enum TaskBody {
+        Sync(Arc<dyn TaskAction>,ExecMode),
+        Dedicated(Arc<dyn TaskAction>,DedicatedThread),
+        Async(Arc<dyn AsyncTaskAction>),
+    }
+
+    impl TaskBody {
+        pub fn sync(t:Arc<dyn TaskAction>,mode:ExecMode) -> TResult<TaskBody> {
+            match mode {
+                ExecMode::DedicatedThread => {
+                    let thread = DedicatedThread::new(t.id())?;
+                    Ok(TaskBody::Dedicated(t,thread))
+                }
+                _ => { Ok(TaskBody::Sync(t,mode)) }
+            }
+        }
+
+        pub async fn run(&self,ctx:ExecContext) -> TResult<()> {
+            match self {
+                TaskBody::Sync(t,_) | TaskBody::Dedicated(t,_) => { t.execute_ctx(&ctx) }
+                TaskBody::Async(t) => { t.execute_ctx(ctx).await }
+            }
+        }
+
+        // 在独立的future中执行，panic不会影响任务循环，超时的执行会被取消
+        pub async fn run_isolated(&self,ctx:ExecContext,timeout:Option<Duration>) -> Result<(),String> {
+            let token = ctx.token().clone();
+            let mut join = match self {
+                // 在工作线程中执行的同步任务体会占住超时所在的线程，设置了超时时改在阻塞线程池中执行
+                TaskBody::Sync(t,mode) if *mode == ExecMode::Blocking || timeout.is_some() => {
+                    let t = t.clone();
+                    tokio::task::spawn_blocking(move || t.execute_ctx(&ctx))
+                }
+                TaskBody::Dedicated(t,thread) => { thread.run(t.clone(),ctx) }
+                _ => {
+                    let body = self.clone();
+                    tokio::spawn(async move { body.run(ctx).await })
+                }
+            };
+            let joined = match timeout {
+                None => { join.await }
+                Some(limit) => {
+                    match tokio::time::timeout(limit,&mut join).await {
+                        Ok(v) => { v }
+                        Err(_) => {
+                            // 异步任务体直接中止；同步任务体无法被打断，通知它退出后不再等待，调度继续进行
+                            token.cancel();
+                            join.abort();
+                            return Err(format!("timeout: exceeded {}ms",limit.as_millis()));
+                        }
+                    }
+                }
+            };
+            match joined {
+                Ok(r) => { r.map_err(|e| e.to_string()) }
+                Err(e) => {
+                    if !e.is_panic() {
+                        return Err(e.to_string());
+                    }
+                    let panic = e.into_panic();
+                    let msg = if let Some(v) = panic.downcast_ref::<&str>() {
+                        String::from(*v)
+                    }else if let Some(v) = panic.downcast_ref::<String>() {
+                        v.clone()
+                    }else {
+                        String::from("unknown panic")
+                    };
+                    Err(format!("panic: {}",msg))
+                }
+            }
+        }
+    }
+
+    type Job = Box<dyn FnOnce() + Send + 'static>;
+
+    // 任务独占的线程，执行依次发送到这个线程，任务结束后线程退出
+    #[derive(Clone)]
+    pub(crate) struct DedicatedThread {
+        jobs:mpsc::Sender<Job>,
+    }
+
+    impl DedicatedThread {
+        fn new(id:u64) -> TResult<DedicatedThread> {
+            let (jobs,rx) = mpsc::channel::<Job>();
+            let spawned = std::thread::Builder::new()
+                .name(format!("grape-timer-{}",id))
+                .spawn(move || {
+                    while let Ok(job) = rx.recv() {
+                        job();
+                    }
+                });
+            match spawned {
+                Ok(_) => { Ok(DedicatedThread{ jobs }) }
+                Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) }
+            }
+        }
+
+        // 在独占线程中执行，panic转交给返回的JoinHandle
+        fn run(&self,t:Arc<dyn TaskAction>,ctx:ExecContext) -> JoinHandle<TResult<()>> {
+            let (tx,rx) = oneshot::channel();
+            let sent = self.jobs.send(Box::new(move || {
+                let r = std::panic::catch_unwind(AssertUnwindSafe(|| t.execute_ctx(&ctx)));
+                let _ = tx.send(r);
+            })).is_ok();
+            tokio::spawn(async move {
+                let stopped = TError::new(TErrorKind::Other(String::from("dedicated thread stopped")));
+                if !sent {
+                    return Err(stopped);
+                }
+                match rx.await {
+                    Ok(Ok(r)) => { r }
+                    Ok(Err(panic)) => { std::panic::resume_unwind(panic) }
+                    Err(_) => { Err(stopped) }
+                }
+            })
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,637 @@
+#[allow(clippy::module_inception)]
+pub mod scheduler {
+    use std::sync::{Mutex,MutexGuard,Arc};
+    use std::time;
+    use std::future::Future;
+    use std::fmt;
+    use crate::schedule::schedule::{TaskAction, ClosuresAction, Schedule, TaskOptions, AsyncTaskAction, AsyncClosuresAction, RunLimit};
+    use crate::errors::errors::{TResult, TError, TErrorKind};
+    use crate::thread::threads::TaskPool;
+    use crate::task::task::{TaskHandle, TaskInfo, OnceHandle};
+    use crate::context::context::ExecContext;
+    use crate::clock::clock::{Clock, SystemClock};
+    use tokio::sync::{oneshot, mpsc};
+    use chrono::{DateTime, Local, TimeZone};
+    use crate::uuid::uuid::{IDMode, IdGenerator};
+    #[cfg(any(test, feature = "testing"))]
+    use crate::hooks::hooks::Hooks;
+    use crate::store::store::{JobStore, JobRecord, JobRegistry, StoreOp, next_fire, write_job};
+    use log::*;
+
+    /// what drives the waits between runs [驱动任务等待的计时器]
+    ///
+    /// `Tokio` sleeps on the configured clock, a tokio timer per task with the system clock.
+    /// `Wheel` puts every wait on a hashed hierarchical timing wheel driven by `threads` threads,
+    /// inserts and cancels are O(1) and waits are rounded up to `resolution`, so hundreds of
+    /// thousands of timers share a few wakeups per tick. The wheel threads sleep until the
+    /// earliest pending wait and read time from the configured clock, a clock that does not
+    /// follow real time, like `ManualClock`, keeps using its own sleeps.
+    /// A waiting task with no overlap limit holds only its wheel entry, no tokio task, and a
+    /// short step is spawned when it is due.
+    /// Run timeouts always use tokio timers.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum TimerBackend {
+        #[default]
+        Tokio,
+        Wheel{ resolution:time::Duration, threads:usize }, // 时间粒度，最小1毫秒，以及时间轮线程数
+    }
+
+    #[derive(Clone)]
+    pub struct Config {
+        pub debug:bool,
+        pub debug_log:String,
+        pub thread_count:i32,
+        pub blocking_thread_count:i32, // ExecMode::Blocking使用的阻塞线程池大小
+        pub id_seed:i64, // 起始ID
+        pub id_type:IDMode,
+        pub default_timeout:Option<time::Duration>, // 任务未设置超时时使用的默认值，None不限制
+        pub timer_backend:TimerBackend, // 任务等待使用的计时器
+        pub clock:Arc<dyn Clock>, // 读取时间和等待使用的时钟，测试时可以换成ManualClock
+        pub job_store:Option<Arc<dyn JobStore>>, // spawn_job创建的任务保存的位置，init_schedule时从中恢复
+    }
+
+    impl Default for Config {
+        fn default() -> Self {
+            Config {
+                debug:false,
+                debug_log:String::new(),
+                thread_count:4,
+                blocking_thread_count:64,
+                id_seed:1, // 起始ID
+                id_type:IDMode::SequenceId,
+                default_timeout:None,
+                timer_backend:TimerBackend::Tokio,
+                clock:Arc::new(SystemClock),
+                job_store:None,
+            }
+        }
+    }
+
+    /// independent scheduler instance [独立的调度器实例，拥有自己的运行时、配置以及ID序列]
+    ///
+    /// Each `Scheduler` owns its own `TaskPool`, `Config` and ID generator,
+    /// cloning a `Scheduler` shares the same instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::{Scheduler, timer::Config, IDMode};
+    /// use std::time;
+    ///
+    /// let sched = Scheduler::new(Config{
+    ///         thread_count: 2,
+    ///         id_seed: 1000,
+    ///         id_type: IDMode::SequenceId,
+    ///         ..Config::default()
+    ///     });
+    ///
+    /// let handle = sched.spawn_ticker(time::Duration::from_millis(5000),2,|id| {
+    ///         println!("on scheduler ticker:{}",id);
+    ///     }).unwrap();
+    /// assert_eq!(handle.id(),1000);
+    /// ```
+    #[derive(Clone)]
+    pub struct Scheduler {
+        config:Arc<Mutex<Config>>,
+        thread_pool:Arc<Mutex<TaskPool>>,
+        ids:Arc<IdGenerator>,
+        jobs:Arc<JobRegistry>,
+    }
+
+    impl Scheduler {
+        /// create a new scheduler by config [通过Config创建一个新的调度器]
+        pub fn new(conf:Config) -> Scheduler {
+            Scheduler {
+                thread_pool:Arc::new(Mutex::new(TaskPool::new(&conf))),
+                ids:Arc::new(IdGenerator::new(conf.id_seed)),
+                jobs:Arc::new(JobRegistry::default()),
+                config:Arc::new(Mutex::new(conf)),
+            }
+        }
+
+        /// get a copy of current config [获取当前配置]
+        pub fn config(&self) -> TResult<Config> {
+            match self.config.lock() {
+                Err(_) => { Err(TError::new(TErrorKind::MutexError)) }
+                Ok(v) => { Ok(v.clone()) }
+            }
+        }
+
+        // 重建运行时以及ID序列
+        pub(crate) fn rebuild(&self,conf:Config) -> TResult<()> {
+            {
+                let mut l_config = self.config.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+                // 旧的任务随重建结束，保留它们的记录用于恢复
+                self.jobs.next_generation();
+                self.thread_pool.lock()
+                    .map_err(|_| TError::new(TErrorKind::MutexError))?
+                    .rebuild(&conf);
+                self.ids.set_seed(conf.id_seed);
+                *l_config = conf;
+            }
+            self.restore_jobs()?;
+            Ok(())
+        }
+
+        // 安装任务活动和执行的观察者
+        #[cfg(any(test, feature = "testing"))]
+        pub(crate) fn set_hooks(&self,hooks:Hooks) -> TResult<()> {
+            self.pool()?.set_hooks(hooks);
+            Ok(())
+        }
+
+        fn pool(&self) -> TResult<MutexGuard<'_,TaskPool>> {
+            match self.thread_pool.lock() {
+                Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) },
+                Ok(v) => { Ok(v) }
+            }
+        }
+
+        fn next_uuid(&self) -> TResult<u64> {
+            let mode = self.config()?.id_type;
+            Ok(self.ids.next_id(mode))
+        }
+
+        fn spawn_action(&self,ft:Arc<dyn TaskAction>) -> TResult<TaskHandle> {
+            self.pool()?.spawn(ft)
+        }
+
+        /// create a new ticker action [创建一个计时器任务]
+        pub fn spawn_ticker(&self,tick:time::Duration, limit:impl Into<RunLimit>, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+            let task_action =  ClosuresAction::new("", self.next_uuid()?, limit, tick, f);
+            self.spawn_action(Arc::new(task_action))
+        }
+
+        /// create a new date action [创建一个日期任务]
+        pub fn spawn_date(&self,dateformate:&str, limit:impl Into<RunLimit>, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+            let task_action =  ClosuresAction::new(dateformate, self.next_uuid()?, limit, time::Duration::from_secs(0), f);
+            self.spawn_action(Arc::new(task_action))
+        }
+
+        /// create a new ticker action with a stateful closure [创建一个使用可变闭包的计时器任务]
+        pub fn spawn_ticker_mut(&self,tick:time::Duration, limit:impl Into<RunLimit>, f: impl FnMut(u64) + Send + 'static) -> TResult<TaskHandle> {
+            let task_action =  ClosuresAction::new_mut("", self.next_uuid()?, limit, tick, f);
+            self.spawn_action(Arc::new(task_action))
+        }
+
+        /// create a new date action with a stateful closure [创建一个使用可变闭包的日期任务]
+        pub fn spawn_date_mut(&self,dateformate:&str, limit:impl Into<RunLimit>, f: impl FnMut(u64) + Send + 'static) -> TResult<TaskHandle> {
+            let task_action =  ClosuresAction::new_mut(dateformate, self.next_uuid()?, limit, time::Duration::from_secs(0), f);
+            self.spawn_action(Arc::new(task_action))
+        }
+
+        /// create a new ticker aligned to wall-clock boundaries [创建一个按时钟边界对齐的计时器任务]
+        pub fn spawn_aligned(&self,period:time::Duration, offset:time::Duration, limit:impl Into<RunLimit>, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+            self.spawn_with(Schedule::aligned(period,offset),limit,TaskOptions::default(),f)
+        }
+
+        /// run a closure once after the delay, a zero delay runs it immediately [延迟一段时间后执行一次，延迟为0时立即执行]
+        pub fn spawn_after<T,F>(&self,delay:time::Duration, f:F) -> TResult<OnceHandle<T>>
+            where
+                F: FnOnce(u64) -> T + Send + 'static,
+                T: Send + 'static,
+        {
+            self.spawn_once(TaskOptions{ initial_delay:Some(delay), ..TaskOptions::default() },f)
+        }
+
+        /// run a closure once at the time, in any time zone [在指定时间执行一次，支持任意时区]
+        ///
+        /// The wait is checked against the clock again when it ends, so a clock set back
+        /// before the time delays the run. A time in the past runs immediately.
+        pub fn spawn_once_at<Tz,T,F>(&self,at:DateTime<Tz>, f:F) -> TResult<OnceHandle<T>>
+            where
+                Tz: TimeZone,
+                F: FnOnce(u64) -> T + Send + 'static,
+                T: Send + 'static,
+        {
+            // 已经过去的时间没有开始等待，初始延迟为0时立即执行
+            let opts = TaskOptions{ start_at:Some(at.with_timezone(&Local)), initial_delay:Some(time::Duration::from_secs(0)), ..TaskOptions::default() };
+            self.spawn_once(opts,f)
+        }
+
+        // 在开始时执行一次，间隔不会被用到
+        fn spawn_once<T,F>(&self,opts:TaskOptions, f:F) -> TResult<OnceHandle<T>>
+            where
+                F: FnOnce(u64) -> T + Send + 'static,
+                T: Send + 'static,
+        {
+            let (tx,rx) = oneshot::channel();
+            let call = Mutex::new(Some((f,tx)));
+            let handle = self.spawn_with(Schedule::Tick(time::Duration::from_secs(1)),1,opts,move |id| {
+                let once = call.lock().ok().and_then(|mut v| v.take());
+                if let Some((f,tx)) = once {
+                    let _ = tx.send(f(id));
+                }
+            })?;
+            Ok(OnceHandle::new(handle,rx))
+        }
+
+        /// create a new action with schedule and options [通过执行计划和可选配置创建一个任务]
+        pub fn spawn_with(&self,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f: impl Fn(u64) + Send+Sync + 'static) -> TResult<TaskHandle> {
+            let task_action = ClosuresAction::with_options(schedule, self.next_uuid()?, limit, opts, f);
+            self.spawn_action(Arc::new(task_action))
+        }
+
+        /// create a new fallible action, errors are retried by the retry policy [创建一个可失败的任务，错误按重试策略处理]
+        pub fn spawn_fallible<E:fmt::Display>(&self,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f: impl Fn(u64) -> Result<(),E> + Send+Sync + 'static) -> TResult<TaskHandle> {
+            let task_action = ClosuresAction::fallible(schedule, self.next_uuid()?, limit, opts, f);
+            self.spawn_action(Arc::new(task_action))
+        }
+
+        /// create a new action receiving the execution context [创建一个接收执行上下文的任务]
+        pub fn spawn_ctx<E:fmt::Display>(&self,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f: impl Fn(&ExecContext) -> Result<(),E> + Send+Sync + 'static) -> TResult<TaskHandle> {
+            let task_action = ClosuresAction::with_context(schedule, self.next_uuid()?, limit, opts, f);
+            self.spawn_action(Arc::new(task_action))
+        }
+
+        /// register a handler that persisted jobs bind to by name [注册可持久化任务按名字绑定的处理函数]
+        pub fn register_handler<E:fmt::Display>(&self,name:&str,f: impl Fn(&ExecContext) -> Result<(),E> + Send+Sync + 'static) -> TResult<()> {
+            self.jobs.register(name,Arc::new(move |ctx| {
+                f(ctx).map_err(|e| TError::new(TErrorKind::Other(e.to_string())))
+            }))
+        }
+
+        /// create a job running a registered handler, kept in the job store [创建执行已注册处理函数的任务，并保存到任务存储]
+        ///
+        /// The record is saved after every run with the runs done and the next fire time,
+        /// and removed when the job ends or is stopped.
+        pub fn spawn_job(&self,handler:&str,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions) -> TResult<TaskHandle> {
+            let limit = limit.into();
+            schedule.validate()?;
+            self.jobs.handler(handler)?;
+            let record = JobRecord::new(self.next_uuid()?,handler,&schedule,&limit);
+            let id = record.id;
+            let store = self.config()?.job_store;
+            // 先保存再创建，创建失败时删除刚保存的记录
+            if let Some(store) = &store {
+                store.save(&record)?;
+            }
+            self.spawn_record(record,schedule,limit,opts).inspect_err(|_| {
+                if let Some(store) = &store {
+                    let _ = store.remove(id);
+                }
+            })
+        }
+
+        /// reload the jobs in the job store and bind them to registered handlers [从任务存储中恢复任务，并绑定到已注册的处理函数]
+        ///
+        /// Restored jobs keep their ids and continue with the runs left from the stored next
+        /// fire time. A fire time missed while the process was down runs once at restore,
+        /// then the job follows its schedule. Jobs without a registered handler stay in the store.
+        /// Returns how many jobs were restored.
+        pub fn restore_jobs(&self) -> TResult<usize> {
+            let store = match self.config()?.job_store {
+                Some(v) => { v }
+                None => { return Ok(0); }
+            };
+            let now = self.pool()?.clock().now();
+            let mut restored = 0;
+            for record in store.load()? {
+                let limit = match record.remaining(&now) {
+                    Some(v) => { v }
+                    None => {
+                        // 已经执行完
+                        store.remove(record.id)?;
+                        continue;
+                    }
+                };
+                let schedule = match record.parse_schedule() {
+                    Ok(v) => { v }
+                    Err(e) => {
+                        error!("bad stored job id:{} schedule:{} err:{}",record.id,record.schedule,e);
+                        continue;
+                    }
+                };
+                let mut opts = TaskOptions::default();
+                if let Some(next) = record.next_run {
+                    // 从保存的下一次执行时间继续，已经错过的立即补执行一次
+                    opts.run_at_start = true;
+                    match (next - now).to_std() {
+                        Ok(_) => { opts.start_at = Some(next) }
+                        Err(_) => {
+                            warn!("job missed run id:{} handler:{} at:{}",record.id,record.handler,next);
+                            opts.initial_delay = Some(time::Duration::from_secs(0));
+                        }
+                    }
+                }
+                let (id,handler) = (record.id,record.handler.clone());
+                // 之后生成的ID不会和恢复的任务重复
+                if let IDMode::SequenceId = self.config()?.id_type {
+                    self.ids.reserve(id as i64);
+                }
+                match self.spawn_record(record,schedule,limit,opts) {
+                    Ok(_) => { restored += 1 }
+                    Err(e) => { error!("restore job failed id:{} handler:{} err:{}",id,handler,e) }
+                }
+            }
+            Ok(restored)
+        }
+
+        // 创建记录对应的任务，执行后更新记录，结束时删除记录，记录已经保存在存储中
+        fn spawn_record(&self,record:JobRecord,schedule:Schedule,limit:RunLimit,opts:TaskOptions) -> TResult<TaskHandle> {
+            let handler = self.jobs.handler(&record.handler)?;
+            let store = self.config()?.job_store;
+            let (clock,timer) = {
+                let pool = self.pool()?;
+                (pool.clock(),pool.timer())
+            };
+            let id = record.id;
+
+            // 存储的读写在阻塞线程中按顺序进行，不占用运行时的工作线程
+            let (tx,rx) = mpsc::unbounded_channel();
+            let base = record.runs_done;
+            let state = Mutex::new(record);
+            let (run_tx,run_timer,run_schedule) = (store.as_ref().map(|_| tx.clone()),timer.clone(),schedule.clone());
+            let task_action = ClosuresAction::with_context(schedule, id, limit, opts, move |ctx| {
+                let result = handler(ctx);
+                if let Some(run_tx) = &run_tx {
+                    let mut record = match state.lock() {
+                        Ok(v) => { v }
+                        Err(e) => { e.into_inner() }
+                    };
+                    record.runs_done = base + ctx.run_index;
+                    record.next_run = next_fire(&run_schedule,ctx,&*clock);
+                    // 最后一次执行后直接删除记录
+                    let op = if ctx.runs_left == Some(0) { StoreOp::Remove(record.id,run_timer.busy()) } else { StoreOp::Save(record.clone(),run_timer.busy()) };
+                    let _ = run_tx.send(op);
+                }
+                result
+            });
+            let handle = self.spawn_action(Arc::new(task_action))?;
+
+            if let Some(store) = store {
+                self.spawn_rt(write_job(store,rx))?;
+                let (jobs,generation,watch) = (self.jobs.clone(),self.jobs.generation(),handle.clone());
+                self.spawn_rt(async move {
+                    let status = watch.wait().await;
+                    // 重建时结束的任务保留记录
+                    if jobs.generation() == generation && status.is_terminal() {
+                        let _ = tx.send(StoreOp::Remove(id,timer.busy()));
+                    }
+                })?;
+            }
+            Ok(handle)
+        }
+
+        /// create a new trait ticker action [创建一个Trait模式计时器任务]
+        pub fn spawn_trait(&self,ft:Arc<dyn TaskAction>) -> TResult<TaskHandle> {
+            self.spawn_action(ft)
+        }
+
+        /// create a new async ticker action [创建一个异步计时器任务]
+        pub fn spawn_async_ticker<F,Fut>(&self,tick:time::Duration, limit:impl Into<RunLimit>, f:F) -> TResult<TaskHandle>
+            where
+                F: Fn(u64) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = ()> + Send + 'static,
+        {
+            self.spawn_async_with(Schedule::Tick(tick),limit,TaskOptions::default(),f)
+        }
+
+        /// create a new async date action [创建一个异步日期任务]
+        pub fn spawn_async_date<F,Fut>(&self,dateformate:&str, limit:impl Into<RunLimit>, f:F) -> TResult<TaskHandle>
+            where
+                F: Fn(u64) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = ()> + Send + 'static,
+        {
+            self.spawn_async_with(Schedule::from(dateformate),limit,TaskOptions::default(),f)
+        }
+
+        /// create a new async action with schedule and options [通过执行计划和可选配置创建一个异步任务]
+        pub fn spawn_async_with<F,Fut>(&self,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f:F) -> TResult<TaskHandle>
+            where
+                F: Fn(u64) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = ()> + Send + 'static,
+        {
+            let task_action = AsyncClosuresAction::new(schedule, self.next_uuid()?, limit, opts, f);
+            self.spawn_async_trait(Arc::new(task_action))
+        }
+
+        /// create a new fallible async action [创建一个可失败的异步任务]
+        pub fn spawn_async_fallible<F,Fut,E>(&self,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f:F) -> TResult<TaskHandle>
+            where
+                F: Fn(u64) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = Result<(),E>> + Send + 'static,
+                E: fmt::Display,
+        {
+            let task_action = AsyncClosuresAction::fallible(schedule, self.next_uuid()?, limit, opts, f);
+            self.spawn_async_trait(Arc::new(task_action))
+        }
+
+        /// create a new async action receiving the execution context [创建一个接收执行上下文的异步任务]
+        pub fn spawn_async_ctx<F,Fut,E>(&self,schedule:Schedule, limit:impl Into<RunLimit>, opts:TaskOptions, f:F) -> TResult<TaskHandle>
+            where
+                F: Fn(ExecContext) -> Fut + Send + Sync + 'static,
+                Fut: Future<Output = Result<(),E>> + Send + 'static,
+                E: fmt::Display,
+        {
+            let task_action = AsyncClosuresAction::with_context(schedule, self.next_uuid()?, limit, opts, f);
+            self.spawn_async_trait(Arc::new(task_action))
+        }
+
+        /// create a new async trait action [创建一个异步Trait模式任务]
+        pub fn spawn_async_trait(&self,ft:Arc<dyn AsyncTaskAction>) -> TResult<TaskHandle> {
+            self.pool()?.spawn_async(ft)
+        }
+
+        /// block on a future in scheduler runtime [在调度器运行时中阻塞执行异步任务]
+        pub fn block_on_rt<F>(&self,future: F) -> TResult<F::Output>
+            where
+                F: Future,
+                F::Output: Send + 'static,
+        {
+            // 不持有锁等待，允许在future中继续创建任务
+            let rt = self.pool()?.rt_handle();
+            Ok(rt.block_on(future))
+        }
+
+        /// spawn a future in scheduler runtime [在调度器运行时中执行异步任务]
+        pub fn spawn_rt<F>(&self,future: F) -> TResult<()>
+            where
+                F: Future + Send + 'static,
+                F::Output: Send + 'static,
+        {
+            self.pool()?.spawn_rt(future);
+            Ok(())
+        }
+
+        /// stop a ticker action [停止一个计时器任务]
+        pub fn stop_ticker(&self,id:u64) -> TResult<()> {
+            self.pool()?.stop_task(id)
+        }
+
+        /// pause a ticker action, the remaining time is kept [暂停一个计时器任务，保留剩余时间]
+        pub fn pause_ticker(&self,id:u64) -> TResult<()> {
+            self.pool()?.pause_task(id)
+        }
+
+        /// resume a paused ticker action [恢复一个暂停的计时器任务]
+        pub fn resume_ticker(&self,id:u64) -> TResult<()> {
+            self.pool()?.resume_task(id)
+        }
+
+        /// list all registered tasks, sorted by id [列出所有已注册的任务，按ID排序]
+        pub fn list_tasks(&self) -> TResult<Vec<TaskInfo>> {
+            self.pool()?.list_tasks()
+        }
+
+        /// inspect a registered task [查询一个已注册任务的信息]
+        pub fn task_info(&self,id:u64) -> TResult<TaskInfo> {
+            self.pool()?.task_info(id)
+        }
+
+        /// stop all tasks with the tag, returns how many were stopped [停止分组内的所有任务，返回停止的数量]
+        pub fn stop_group(&self,tag:&str) -> TResult<usize> {
+            self.pool()?.stop_group(tag)
+        }
+
+        /// pause all tasks with the tag [暂停分组内的所有任务]
+        pub fn pause_group(&self,tag:&str) -> TResult<usize> {
+            self.pool()?.pause_group(tag)
+        }
+
+        /// resume all tasks with the tag [恢复分组内的所有任务]
+        pub fn resume_group(&self,tag:&str) -> TResult<usize> {
+            self.pool()?.resume_group(tag)
+        }
+
+        /// list all tasks with the tag, sorted by id [列出分组内的所有任务]
+        pub fn list_group(&self,tag:&str) -> TResult<Vec<TaskInfo>> {
+            self.pool()?.list_group(tag)
+        }
+
+        /// change the schedule of a running task [修改一个任务的间隔或日期格式]
+        pub fn reschedule(&self,id:u64,schedule:Schedule) -> TResult<()> {
+            self.pool()?.reschedule_task(id,schedule)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scheduler::scheduler::{Scheduler, Config};
+    use crate::IDMode;
+    use crate::task::task::TaskStatus;
+    use crate::schedule::schedule::Schedule;
+    use std::time;
+    use std::sync::{Arc, Mutex};
+    use chrono::{Local, Utc, FixedOffset, TimeZone};
+    use crate::errors::errors::TErrorKind;
+    use crate::testing::testing::TestScheduler;
+
+    #[test]
+    fn test_independent_ids() {
+        let first = Scheduler::new(Config{ id_seed:100, ..Config::default() });
+        let second = Scheduler::new(Config{ id_seed:500, thread_count:1, ..Config::default() });
+
+        assert_eq!(first.spawn_ticker(time::Duration::from_secs(60),1,|_| {}).unwrap().id(),100);
+        assert_eq!(first.spawn_ticker(time::Duration::from_secs(60),1,|_| {}).unwrap().id(),101);
+        assert_eq!(second.spawn_date("Day 05:00:00",1,|_| {}).unwrap().id(),500);
+
+        let stamp = Scheduler::new(Config{ id_type:IDMode::TimestampId, ..Config::default() });
+        assert!(stamp.spawn_ticker(time::Duration::from_secs(60),1,|_| {}).unwrap().id() > 500);
+    }
+
+    #[test]
+    fn test_spawn_aligned() {
+        let midnight = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(midnight + chrono::Duration::milliseconds(130));
+        let handle = test.spawn_aligned(time::Duration::from_millis(200),time::Duration::from_millis(50),2,|_| {}).unwrap();
+        assert_eq!(handle.info().schedule,Schedule::from("Every 200ms +50ms"));
+        test.advance(time::Duration::from_secs(1));
+        assert_eq!(handle.status(),TaskStatus::Finished);
+
+        // 从本地零点开始对齐到 200ms 的边界再偏移 50ms
+        let runs = test.runs_of(handle.id()).iter().map(|v| (v.started_at - midnight).num_milliseconds()).collect::<Vec<_>>();
+        assert_eq!(runs,vec![250,450]);
+    }
+
+    #[test]
+    fn test_one_shot() {
+        let sched = Scheduler::new(Config{ thread_count:1, ..Config::default() });
+
+        // 任意时区的时间
+        let at = Utc::now().with_timezone(&FixedOffset::west(3 * 3600)) + chrono::Duration::milliseconds(50);
+        let begin = time::Instant::now();
+        let once = sched.spawn_once_at(at,|id| id + 1).unwrap();
+        let id = once.id();
+        assert_eq!(sched.block_on_rt(once.result()).unwrap().unwrap(),id + 1);
+        assert!(begin.elapsed() >= time::Duration::from_millis(45));
+
+        // 执行前取消
+        let once = sched.spawn_after(time::Duration::from_secs(60),|_| {}).unwrap();
+        once.cancel();
+        let err = sched.block_on_rt(async move { once.await }).unwrap().unwrap_err();
+        assert!(matches!(err.kind(),TErrorKind::Cancelled));
+
+        // panic作为错误返回
+        let once = sched.spawn_after(time::Duration::from_millis(5),|_| -> u32 { panic!("boom") }).unwrap();
+        let err = sched.block_on_rt(once.result()).unwrap().unwrap_err();
+        assert_eq!(err.last_error(),"panic: boom");
+    }
+
+    #[test]
+    fn test_one_shot_clock() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+
+        // 延迟为0时立即执行，不需要推进时钟
+        let now = test.spawn_after(time::Duration::from_secs(0),|id| id).unwrap();
+        test.settle();
+        assert_eq!(test.runs_of(now.id()).iter().map(|v| v.started_at).collect::<Vec<_>>(),vec![start]);
+
+        // 时钟被调回后，到时间才执行
+        let at = start + chrono::Duration::seconds(60);
+        let once = test.spawn_once_at(at,|id| id).unwrap();
+        test.clock().set(start - chrono::Duration::seconds(30));
+        test.advance(time::Duration::from_secs(60));
+        assert!(test.runs_of(once.id()).is_empty());
+        assert_eq!(once.handle().status(),TaskStatus::Scheduled);
+        test.advance(time::Duration::from_secs(30));
+        assert_eq!(test.runs_of(once.id()).iter().map(|v| v.started_at).collect::<Vec<_>>(),vec![at]);
+        assert_eq!(once.handle().status(),TaskStatus::Finished);
+
+        // 已经过去的时间立即执行
+        let past = test.spawn_once_at(start,|id| id).unwrap();
+        test.settle();
+        assert_eq!(test.runs_of(past.id()).len(),1);
+    }
+
+    #[test]
+    fn test_stateful_closure() {
+        let sched = Scheduler::new(Config{ thread_count:1, ..Config::default() });
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        // Cell不是Sync，只能由任务自己持有
+        let record = seen.clone();
+        let counter = std::cell::Cell::new(0);
+        let mut history = Vec::new();
+        let handle = sched.spawn_ticker_mut(time::Duration::from_millis(10),3,move |_| {
+            counter.set(counter.get() + 1);
+            history.push(counter.get());
+            *record.lock().unwrap() = history.clone();
+        }).unwrap();
+        assert_eq!(sched.block_on_rt(handle.wait()).unwrap(),TaskStatus::Finished);
+        assert_eq!(*seen.lock().unwrap(),vec![1,2,3]);
+
+        // panic之后保留原来的状态继续执行
+        let record = seen.clone();
+        let mut count = 0;
+        let handle = sched.spawn_ticker_mut(time::Duration::from_millis(10),3,move |_| {
+            count += 1;
+            record.lock().unwrap().push(count);
+            if count == 1 {
+                panic!("first run");
+            }
+        }).unwrap();
+        assert_eq!(sched.block_on_rt(handle.wait()).unwrap(),TaskStatus::Finished);
+        assert_eq!(*seen.lock().unwrap(),vec![1,2,3,1,2,3]);
+        assert_eq!(handle.info().last_error,Some(String::from("panic: first run")));
+
+        // 任务进入等待后下一次执行时间是第二天零点
+        let test = TestScheduler::new(Local.ymd(2026,3,2).and_hms(4,0,0));
+        let handle = test.spawn_date_mut("Day 00:00:00",0,|_| {}).unwrap();
+        test.settle();
+        assert_eq!(handle.next_run(),Some(Local.ymd(2026,3,3).and_hms(0,0,0)));
+        handle.cancel();
+    }
+}
@@ -0,0 +1,376 @@
+#[allow(clippy::module_inception)]
+pub mod store {
+    use std::sync::{Arc,Mutex};
+    use std::sync::atomic::{AtomicU64,Ordering};
+    use std::collections::{BTreeMap,HashMap};
+    use std::fs;
+    use std::path::{Path,PathBuf};
+    use std::time::Duration;
+    use chrono::{DateTime,Local};
+    use serde::{Serialize,Deserialize};
+    use crate::schedule::schedule::{Schedule, RunLimit};
+    use crate::context::context::ExecContext;
+    use crate::parsers::parsers::parser_next_with;
+    use crate::clock::clock::Clock;
+    use crate::errors::errors::{TError, TResult, TErrorKind};
+    use crate::hooks::hooks::Busy;
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use log::*;
+
+    /// body of a persisted job, registered by name [可持久化任务的处理函数，按名字注册]
+    pub type JobHandler = Arc<dyn Fn(&ExecContext) -> TResult<()> + Send + Sync>;
+
+    /// a task definition kept in a `JobStore` [保存在JobStore中的任务定义]
+    ///
+    /// `schedule` is a date format, or `Tick <ms>` for an interval.
+    /// The run limit is kept as the total count and deadline, `runs_done` counts
+    /// every run across restarts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::store::store::JobRecord;
+    /// use grapeTimerR::schedule::schedule::{Schedule, RunLimit};
+    /// use std::time;
+    ///
+    /// let mut record = JobRecord::new(1,"flush",&Schedule::Tick(time::Duration::from_secs(5)),&RunLimit::Times(3));
+    /// assert_eq!(record.schedule,"Tick 5000");
+    /// record.runs_done = 2;
+    /// assert_eq!(record.remaining(&chrono::Local::now()),Some(RunLimit::Times(1)));
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct JobRecord {
+        pub id:u64,
+        pub handler:String, // 注册的处理函数名
+        pub schedule:String,
+        pub limit_times:Option<u64>, // 总的执行次数，None不限制
+        pub limit_until:Option<DateTime<Local>>, // 执行截止时间
+        pub runs_done:u64,
+        pub next_run:Option<DateTime<Local>>, // 下一次执行的时间
+    }
+
+    impl JobRecord {
+        pub fn new(id:u64,handler:&str,schedule:&Schedule,limit:&RunLimit) -> JobRecord {
+            JobRecord {
+                id,
+                handler:String::from(handler),
+                schedule:schedule_spec(schedule),
+                limit_times:limit.times(),
+                limit_until:limit.until(),
+                runs_done:0,
+                next_run:None,
+            }
+        }
+
+        /// parse the stored schedule [解析保存的执行计划]
+        pub fn parse_schedule(&self) -> TResult<Schedule> {
+            let schedule = match self.schedule.strip_prefix("Tick ") {
+                Some(ms) => {
+                    let ms = ms.trim().parse::<u64>().map_err(|_| TError::new(TErrorKind::BadFormat))?;
+                    Schedule::Tick(Duration::from_millis(ms))
+                }
+                None => { Schedule::Date(self.schedule.clone()) }
+            };
+            schedule.validate()?;
+            Ok(schedule)
+        }
+
+        /// limit for the runs still to do, None when nothing is left [剩余的执行限制，已经执行完时返回None]
+        pub fn remaining(&self,now:&DateTime<Local>) -> Option<RunLimit> {
+            if self.limit_until.is_some_and(|v| *now > v) {
+                return None;
+            }
+            let times = match self.limit_times {
+                Some(n) if n <= self.runs_done => { return None; }
+                Some(n) => { Some(n - self.runs_done) }
+                None => { None }
+            };
+            Some(match (times,self.limit_until) {
+                (Some(n),Some(v)) => { RunLimit::TimesOrUntil(n,v) }
+                (Some(n),None) => { RunLimit::Times(n) }
+                (None,Some(v)) => { RunLimit::Until(v) }
+                (None,None) => { RunLimit::Forever }
+            })
+        }
+    }
+
+    fn schedule_spec(schedule:&Schedule) -> String {
+        match schedule {
+            Schedule::Tick(d) => { format!("Tick {}",d.as_millis()) }
+            Schedule::Date(v) => { v.clone() }
+        }
+    }
+
+    // 一次执行之后的下一次执行时间
+    pub(crate) fn next_fire(schedule:&Schedule,ctx:&ExecContext,clock:&dyn Clock) -> Option<DateTime<Local>> {
+        match schedule {
+            Schedule::Tick(d) => { chrono::Duration::from_std(*d).ok().map(|v| ctx.scheduled_at + v) }
+            Schedule::Date(v) => { parser_next_with(v,clock).ok() }
+        }
+    }
+
+    /// where persisted jobs are kept [保存可持久化任务的位置]
+    ///
+    /// The scheduler saves a record when a job is created and after every run,
+    /// and removes it when the job ends.
+    pub trait JobStore : Send + Sync {
+        fn load(&self) -> TResult<Vec<JobRecord>>;
+        fn save(&self,record:&JobRecord) -> TResult<()>;
+        fn remove(&self,id:u64) -> TResult<()>;
+    }
+
+    /// jobs kept in memory, lost with the process [保存在内存中的任务，进程结束后丢失]
+    #[derive(Default)]
+    pub struct MemoryJobStore {
+        jobs:Mutex<BTreeMap<u64,JobRecord>>,
+    }
+
+    impl MemoryJobStore {
+        pub fn new() -> MemoryJobStore {
+            MemoryJobStore::default()
+        }
+    }
+
+    impl JobStore for MemoryJobStore {
+        fn load(&self) -> TResult<Vec<JobRecord>> {
+            let jobs = self.jobs.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            Ok(jobs.values().cloned().collect())
+        }
+
+        fn save(&self,record:&JobRecord) -> TResult<()> {
+            let mut jobs = self.jobs.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            jobs.insert(record.id,record.clone());
+            Ok(())
+        }
+
+        fn remove(&self,id:u64) -> TResult<()> {
+            let mut jobs = self.jobs.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            jobs.remove(&id);
+            Ok(())
+        }
+    }
+
+    /// jobs kept in a JSON file, rewritten on every change [保存在JSON文件中的任务，每次变化时重写文件]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::store::store::{FileJobStore, JobStore, JobRecord};
+    /// use grapeTimerR::schedule::schedule::{Schedule, RunLimit};
+    ///
+    /// let path = std::env::temp_dir().join(format!("grape-jobs-doc-{}.json",std::process::id()));
+    /// let store = FileJobStore::open(&path).unwrap();
+    /// store.save(&JobRecord::new(7,"daily_reset",&Schedule::from("Day 05:00:00"),&RunLimit::Forever)).unwrap();
+    ///
+    /// // 重新打开时读取文件中的任务
+    /// let reopened = FileJobStore::open(&path).unwrap();
+    /// assert_eq!(reopened.load().unwrap()[0].handler,"daily_reset");
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub struct FileJobStore {
+        path:PathBuf,
+        jobs:Mutex<BTreeMap<u64,JobRecord>>,
+    }
+
+    impl FileJobStore {
+        /// open a store, a missing file starts empty [打开存储文件，文件不存在时为空]
+        pub fn open(path:impl AsRef<Path>) -> TResult<FileJobStore> {
+            let path = path.as_ref().to_path_buf();
+            let jobs = match fs::read_to_string(&path) {
+                Ok(v) if v.trim().is_empty() => { BTreeMap::new() }
+                Ok(v) => {
+                    let records:Vec<JobRecord> = serde_json::from_str(&v).map_err(|e| TError::new(TErrorKind::Other(e.to_string())))?;
+                    records.into_iter().map(|r| (r.id,r)).collect()
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => { BTreeMap::new() }
+                Err(e) => { return Err(TError::new(TErrorKind::Other(e.to_string()))); }
+            };
+            Ok(FileJobStore { path, jobs:Mutex::new(jobs) })
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        // 先写临时文件再替换，避免写到一半的文件
+        fn write(&self,jobs:&BTreeMap<u64,JobRecord>) -> TResult<()> {
+            let records = jobs.values().collect::<Vec<&JobRecord>>();
+            let data = serde_json::to_string_pretty(&records).map_err(|e| TError::new(TErrorKind::Other(e.to_string())))?;
+            let mut tmp = self.path.clone().into_os_string();
+            tmp.push(".tmp");
+            fs::write(&tmp,data).map_err(|e| TError::new(TErrorKind::Other(e.to_string())))?;
+            fs::rename(&tmp,&self.path).map_err(|e| TError::new(TErrorKind::Other(e.to_string())))
+        }
+    }
+
+    impl JobStore for FileJobStore {
+        fn load(&self) -> TResult<Vec<JobRecord>> {
+            let jobs = self.jobs.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            Ok(jobs.values().cloned().collect())
+        }
+
+        fn save(&self,record:&JobRecord) -> TResult<()> {
+            let mut jobs = self.jobs.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            jobs.insert(record.id,record.clone());
+            self.write(&jobs)
+        }
+
+        fn remove(&self,id:u64) -> TResult<()> {
+            let mut jobs = self.jobs.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            if jobs.remove(&id).is_some() {
+                self.write(&jobs)?;
+            }
+            Ok(())
+        }
+    }
+
+    // 执行之后对任务存储的修改，Busy让测试调度器等待写入完成
+    pub(crate) enum StoreOp {
+        Save(JobRecord,Busy),
+        Remove(u64,Busy),
+    }
+
+    // 按顺序在阻塞线程中写入一个任务的修改，删除记录后结束
+    pub(crate) async fn write_job(store:Arc<dyn JobStore>,mut ops:UnboundedReceiver<StoreOp>) {
+        while let Some(op) = ops.recv().await {
+            let store = store.clone();
+            let removed = matches!(op,StoreOp::Remove(..));
+            let written = tokio::task::spawn_blocking(move || {
+                match op {
+                    StoreOp::Save(record,_busy) => { store.save(&record).map_err(|e| (record.id,e)) }
+                    StoreOp::Remove(id,_busy) => { store.remove(id).map_err(|e| (id,e)) }
+                }
+            }).await;
+            match written {
+                Ok(Err((id,e))) => { error!("write job failed id:{} err:{}",id,e) }
+                Err(e) => { error!("write job failed err:{}",e) }
+                Ok(Ok(_)) => {}
+            }
+            if removed {
+                break;
+            }
+        }
+    }
+
+    // 调度器注册的处理函数，generation在重建时增加，旧的任务结束时不再删除记录
+    #[derive(Default)]
+    pub(crate) struct JobRegistry {
+        handlers:Mutex<HashMap<String,JobHandler>>,
+        generation:AtomicU64,
+    }
+
+    impl JobRegistry {
+        pub fn register(&self,name:&str,handler:JobHandler) -> TResult<()> {
+            let mut handlers = self.handlers.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            handlers.insert(String::from(name),handler);
+            Ok(())
+        }
+
+        pub fn handler(&self,name:&str) -> TResult<JobHandler> {
+            let handlers = self.handlers.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            handlers.get(name).cloned().ok_or_else(|| TError::new(TErrorKind::HandlerNotFound))
+        }
+
+        pub fn generation(&self) -> u64 {
+            self.generation.load(Ordering::SeqCst)
+        }
+
+        pub fn next_generation(&self) {
+            self.generation.fetch_add(1,Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::store::{JobStore, JobRecord, FileJobStore, MemoryJobStore};
+    use crate::testing::testing::TestScheduler;
+    use crate::scheduler::scheduler::Config;
+    use crate::schedule::schedule::{Schedule, RunLimit, TaskOptions};
+    use crate::errors::errors::TErrorKind;
+    use chrono::{Local, TimeZone, Duration};
+    use std::sync::Arc;
+    use std::time;
+
+    #[test]
+    fn test_file_store() {
+        let path = std::env::temp_dir().join(format!("grape-jobs-test-{}.json",std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = FileJobStore::open(&path).unwrap();
+        assert!(store.load().unwrap().is_empty());
+
+        let until = Local.ymd(2026,4,1).and_hms(0,0,0);
+        let mut daily = JobRecord::new(3,"daily",&Schedule::from("Day 05:00:00"),&RunLimit::TimesOrUntil(30,until));
+        daily.runs_done = 12;
+        daily.next_run = Some(Local.ymd(2026,3,14).and_hms(5,0,0));
+        store.save(&daily).unwrap();
+        store.save(&JobRecord::new(4,"flush",&Schedule::Tick(time::Duration::from_millis(1500)),&RunLimit::Forever)).unwrap();
+        store.remove(4).unwrap();
+
+        let reopened = FileJobStore::open(&path).unwrap();
+        let records = reopened.load().unwrap();
+        assert_eq!(records,vec![daily.clone()]);
+        assert_eq!(records[0].parse_schedule().unwrap(),Schedule::from("Day 05:00:00"));
+        assert_eq!(records[0].remaining(&Local.ymd(2026,3,14).and_hms(0,0,0)),Some(RunLimit::TimesOrUntil(18,until)));
+        assert_eq!(records[0].remaining(&Local.ymd(2026,4,2).and_hms(0,0,0)),None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_restore_jobs() {
+        // 2026-03-02是周一
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let store = Arc::new(MemoryJobStore::new());
+        let conf = Config{ job_store:Some(store.clone()), ..Config::default() };
+
+        let first = TestScheduler::with_config(start,conf.clone());
+        first.register_handler("reset",|_| Ok::<(),String>(())).unwrap();
+        let handle = first.spawn_job("reset",Schedule::from("Day 05:00:00"),5,TaskOptions::default()).unwrap();
+        assert!(matches!(first.spawn_job("missing",Schedule::from("Day 05:00:00"),5,TaskOptions::default()).unwrap_err().kind(),TErrorKind::HandlerNotFound));
+
+        first.advance(time::Duration::from_secs(3600 * 24 * 2));
+        let records = store.load().unwrap();
+        assert_eq!(records.len(),1);
+        assert_eq!((records[0].id,records[0].runs_done),(handle.id(),2));
+        assert_eq!(records[0].next_run,Some(Local.ymd(2026,3,4).and_hms(5,0,0)));
+
+        // 重启后按名字重新绑定，继续剩下的次数
+        let second = TestScheduler::with_config(start + Duration::days(2),conf);
+        second.register_handler("reset",|_| Ok::<(),String>(())).unwrap();
+        assert_eq!(second.restore_jobs().unwrap(),1);
+        second.advance(time::Duration::from_secs(3600 * 24 * 10));
+        let runs = second.log();
+        assert_eq!(runs.len(),3);
+        assert_eq!(runs[0].started_at,Local.ymd(2026,3,4).and_hms(5,0,0));
+        assert!(runs.iter().all(|v| v.id == handle.id()));
+        assert!(store.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rebuild_restores_file_jobs() {
+        let path = std::env::temp_dir().join(format!("grape-jobs-rebuild-{}.json",std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        // 2026-03-02是周一
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let first = TestScheduler::with_config(start,Config{ job_store:Some(Arc::new(FileJobStore::open(&path).unwrap())), ..Config::default() });
+        first.register_handler("reset",|_| Ok::<(),String>(())).unwrap();
+        let id = first.spawn_job("reset",Schedule::from("Day 05:00:00"),5,TaskOptions::default()).unwrap().id();
+        first.advance(time::Duration::from_secs(3600 * 24 * 2));
+        assert_eq!(first.log().len(),2);
+        drop(first);
+
+        // 停机期间错过了3月4日05:00，重建时立即补执行一次，之后按日期执行
+        let second = TestScheduler::with_config(Local.ymd(2026,3,4).and_hms(8,0,0),Config{ job_store:Some(Arc::new(FileJobStore::open(&path).unwrap())), ..Config::default() });
+        second.register_handler("reset",|_| Ok::<(),String>(())).unwrap();
+        second.rebuild(second.config().unwrap()).unwrap();
+        let info = second.task_info(id).unwrap();
+        assert_eq!(info.id,id);
+        assert_ne!(second.spawn_ticker(time::Duration::from_secs(3600 * 24 * 30),1,|_| {}).unwrap().id(),id);
+
+        second.advance(time::Duration::from_secs(3600 * 24 * 3));
+        let runs = second.runs_of(id).iter().map(|v| v.started_at).collect::<Vec<_>>();
+        assert_eq!(runs,vec![Local.ymd(2026,3,4).and_hms(8,0,0),Local.ymd(2026,3,5).and_hms(5,0,0),Local.ymd(2026,3,6).and_hms(5,0,0)]);
+        assert!(FileJobStore::open(&path).unwrap().load().unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,597 @@
+#[allow(clippy::module_inception)]
+pub mod task {
+    use std::sync::{Arc,Mutex};
+    use std::sync::atomic::{AtomicBool,AtomicU32,AtomicU64,Ordering};
+    use std::collections::HashMap;
+    use std::future::{Future,IntoFuture};
+    use std::pin::Pin;
+    use std::fmt;
+    use tokio::sync::{Notify,watch,oneshot};
+    use tokio::time;
+    use chrono::{DateTime,Local};
+    use crate::schedule::schedule::{Schedule, RunLimit};
+    use crate::errors::errors::{TResult, TError, TErrorKind};
+    use crate::wheel::wheel::Timer;
+    use crate::hooks::hooks::Waiters;
+    use crate::clock::clock::Clock;
+
+    /// task status [任务状态]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TaskStatus {
+        Scheduled, // 等待下一次执行
+        Running,   // 正在执行
+        Paused,    // 已暂停
+        Finished,  // 次数用完，自然结束
+        Cancelled, // 被停止
+        Failed,    // 按失败策略停止
+    }
+
+    impl TaskStatus {
+        /// the task will never run again [任务已经结束，不会再执行]
+        pub fn is_terminal(&self) -> bool {
+            matches!(self,TaskStatus::Finished | TaskStatus::Cancelled | TaskStatus::Failed)
+        }
+    }
+
+    /// snapshot of a registered task [任务信息快照，用于管理面板和健康检查]
+    #[derive(Debug, Clone)]
+    pub struct TaskInfo {
+        pub id:u64,
+        pub tags:Vec<String>,
+        pub schedule:Schedule,
+        pub run_limit:RunLimit,
+        pub runs_left:Option<u64>, // 剩余的执行次数，不限次数时为None
+        pub run_count:u64,
+        pub last_run:Option<DateTime<Local>>,
+        pub next_run:Option<DateTime<Local>>,
+        pub status:TaskStatus,
+        pub failures:u32, // 连续失败的次数
+        pub last_error:Option<String>, // 最后一次失败的错误或panic信息
+    }
+
+    // 任务等待结束的原因
+    pub(crate) enum Wake {
+        Elapsed,
+        Interrupted, // 暂停、恢复等状态变化
+        Cancelled,
+    }
+
+    // 状态变化时的回调，时间轮驱动的任务没有等待中的任务循环，由它收回时间轮上的等待
+    pub(crate) type OnChange = Arc<dyn Fn() + Send + Sync>;
+
+    // 每个任务独立的控制句柄，任务循环和TaskHandle共享
+    pub(crate) struct TaskControl {
+        id:u64,
+        cancelled:AtomicBool,
+        paused:AtomicBool,
+        notify:Notify,
+        status:watch::Sender<TaskStatus>,
+        run_count:AtomicU64,
+        run_limit:RunLimit,
+        tags:Vec<String>,
+        last_run:Mutex<Option<DateTime<Local>>>,
+        next_run:Mutex<Option<DateTime<Local>>>,
+        schedule:Mutex<Schedule>,
+        rescheduled:AtomicBool,
+        changes:AtomicU64, // 状态变化的次数，等待前用来发现错过的通知
+        waiters:Waiters, // 测试调度器计算被通知唤醒的等待
+        failures:AtomicU32,
+        last_error:Mutex<Option<String>>,
+        running:AtomicU32, // 正在执行的数量
+        timer:Timer,
+        on_change:Mutex<Option<OnChange>>,
+    }
+
+    impl TaskControl {
+        pub fn new(id:u64,schedule:Schedule,run_limit:RunLimit,tags:Vec<String>) -> TaskControl {
+            let (tx,_) = watch::channel(TaskStatus::Scheduled);
+            TaskControl {
+                id,
+                cancelled:AtomicBool::new(false),
+                paused:AtomicBool::new(false),
+                notify:Notify::new(),
+                status:tx,
+                run_count:AtomicU64::new(0),
+                run_limit,
+                tags,
+                last_run:Mutex::new(None),
+                next_run:Mutex::new(None),
+                schedule:Mutex::new(schedule),
+                rescheduled:AtomicBool::new(false),
+                changes:AtomicU64::new(0),
+                waiters:Waiters::new(),
+                failures:AtomicU32::new(0),
+                last_error:Mutex::new(None),
+                running:AtomicU32::new(0),
+                timer:Timer::default(),
+                on_change:Mutex::new(None),
+            }
+        }
+
+        // 使用调度器配置的计时器等待
+        pub fn with_timer(mut self,timer:Timer) -> TaskControl {
+            self.timer = timer;
+            self
+        }
+
+        pub fn clock(&self) -> &Arc<dyn Clock> {
+            self.timer.clock()
+        }
+
+        pub fn timer(&self) -> &Timer {
+            &self.timer
+        }
+
+        pub fn id(&self) -> u64 {
+            self.id
+        }
+
+        pub fn status(&self) -> TaskStatus {
+            *self.status.borrow()
+        }
+
+        // 结束状态不会再被修改
+        pub fn set_status(&self,status:TaskStatus) {
+            self.status.send_if_modified(|v| {
+                if v.is_terminal() || *v == status {
+                    return false;
+                }
+                *v = status;
+                true
+            });
+        }
+
+        // 执行完成后回到等待状态，暂停中的任务保持暂停
+        pub fn set_idle(&self) {
+            if self.is_paused() {
+                self.set_status(TaskStatus::Paused);
+            }else {
+                self.set_status(TaskStatus::Scheduled);
+            }
+        }
+
+        // 开始一次执行
+        pub fn begin_run(&self) {
+            self.running.fetch_add(1,Ordering::SeqCst);
+            self.set_status(TaskStatus::Running);
+        }
+
+        // 结束一次执行，没有正在执行的时回到等待状态
+        pub fn end_run(&self) {
+            if self.running.fetch_sub(1,Ordering::SeqCst) == 1 {
+                self.set_idle();
+            }
+        }
+
+        pub fn cancel(&self) {
+            self.cancelled.store(true,Ordering::SeqCst);
+            self.set_status(TaskStatus::Cancelled);
+            self.set_next_run(None);
+            self.wake_all();
+        }
+
+        pub fn finish(&self) {
+            self.set_status(TaskStatus::Finished);
+            self.set_next_run(None);
+        }
+
+        pub fn fail(&self) {
+            self.set_status(TaskStatus::Failed);
+            self.set_next_run(None);
+            // 唤醒等待中的任务循环
+            self.wake_all();
+        }
+
+        pub fn record_success(&self) {
+            self.failures.store(0,Ordering::SeqCst);
+        }
+
+        // 记录一次失败，返回连续失败的次数
+        pub fn record_failure(&self,err:String) -> u32 {
+            self.record_error(err);
+            self.failures.fetch_add(1,Ordering::SeqCst) + 1
+        }
+
+        // 只记录错误信息，不计入连续失败
+        pub fn record_error(&self,err:String) {
+            if let Ok(mut v) = self.last_error.lock() {
+                *v = Some(err);
+            }
+        }
+
+        pub fn last_error(&self) -> Option<String> {
+            match self.last_error.lock() {
+                Ok(v) => { v.clone() }
+                Err(_) => { None }
+            }
+        }
+
+        pub fn is_cancelled(&self) -> bool {
+            self.cancelled.load(Ordering::SeqCst)
+        }
+
+        pub fn pause(&self) {
+            self.paused.store(true,Ordering::SeqCst);
+            if self.status() != TaskStatus::Running {
+                self.set_status(TaskStatus::Paused);
+            }
+            self.wake_all();
+        }
+
+        pub fn resume(&self) {
+            self.paused.store(false,Ordering::SeqCst);
+            if self.status() == TaskStatus::Paused {
+                self.set_status(TaskStatus::Scheduled);
+            }
+            self.wake_all();
+        }
+
+        pub fn is_paused(&self) -> bool {
+            self.paused.load(Ordering::SeqCst)
+        }
+
+        pub fn run_count(&self) -> u64 {
+            self.run_count.load(Ordering::SeqCst)
+        }
+
+        pub fn add_run(&self) -> u64 {
+            if let Ok(mut v) = self.last_run.lock() {
+                *v = Some(self.clock().now());
+            }
+            self.run_count.fetch_add(1,Ordering::SeqCst) + 1
+        }
+
+        pub fn run_limit(&self) -> RunLimit {
+            self.run_limit
+        }
+
+        pub fn runs_left(&self) -> Option<u64> {
+            self.run_limit.runs_left(self.run_count())
+        }
+
+        pub fn has_tag(&self,tag:&str) -> bool {
+            self.tags.iter().any(|v| v == tag)
+        }
+
+        pub fn last_run(&self) -> Option<DateTime<Local>> {
+            match self.last_run.lock() {
+                Ok(v) => { *v }
+                Err(_) => { None }
+            }
+        }
+
+        pub fn info(&self) -> TaskInfo {
+            TaskInfo {
+                id:self.id,
+                tags:self.tags.clone(),
+                schedule:self.schedule(),
+                run_limit:self.run_limit,
+                runs_left:self.runs_left(),
+                run_count:self.run_count(),
+                last_run:self.last_run(),
+                next_run:self.next_run(),
+                status:self.status(),
+                failures:self.failures.load(Ordering::SeqCst),
+                last_error:self.last_error(),
+            }
+        }
+
+        pub fn next_run(&self) -> Option<DateTime<Local>> {
+            match self.next_run.lock() {
+                Ok(v) => { *v }
+                Err(_) => { None }
+            }
+        }
+
+        pub fn set_next_run(&self,next:Option<DateTime<Local>>) {
+            if let Ok(mut v) = self.next_run.lock() {
+                *v = next;
+            }
+        }
+
+        pub fn schedule(&self) -> Schedule {
+            match self.schedule.lock() {
+                Ok(v) => { v.clone() }
+                Err(e) => { e.into_inner().clone() }
+            }
+        }
+
+        pub fn reschedule(&self,schedule:Schedule) -> TResult<()> {
+            schedule.validate()?;
+            if let Ok(mut v) = self.schedule.lock() {
+                *v = schedule;
+            }
+            self.rescheduled.store(true,Ordering::SeqCst);
+            self.wake_all();
+            Ok(())
+        }
+
+        // 是否刚被重新设置过执行计划
+        pub fn take_rescheduled(&self) -> bool {
+            self.rescheduled.swap(false,Ordering::SeqCst)
+        }
+
+        // 唤醒所有等待者，任务循环和非串行模式下的执行可能同时在等待
+        fn wake_all(&self) {
+            self.waiters.wake(&self.timer);
+            self.changes.fetch_add(1,Ordering::SeqCst);
+            self.notify.notify_waiters();
+            // 在锁外调用，回调中可以再读取任务的状态
+            let on_change = self.on_change.lock().ok().and_then(|v| v.clone());
+            if let Some(f) = on_change {
+                f();
+            }
+        }
+
+        pub fn set_on_change(&self,f:Option<OnChange>) {
+            if let Ok(mut v) = self.on_change.lock() {
+                *v = f;
+            }
+        }
+
+        pub fn changes(&self) -> u64 {
+            self.changes.load(Ordering::SeqCst)
+        }
+
+        // 等待指定的时间，被取消或暂停时提前唤醒
+        pub async fn sleep(&self,dur:time::Duration) -> Wake {
+            self.sleep_since(dur,self.changes()).await
+        }
+
+        // 同sleep，seen之后状态已经变化时立即返回
+        pub async fn sleep_since(&self,dur:time::Duration,seen:u64) -> Wake {
+            // 先登记再检查状态，检查之后的通知不会丢失
+            let _waiting = self.waiters.register(&self.timer);
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return Wake::Cancelled;
+            }
+            if self.is_paused() || self.changes() != seen {
+                return Wake::Interrupted;
+            }
+
+            tokio::select! {
+                _ = self.timer.sleep(dur) => { Wake::Elapsed }
+                _ = notified => {
+                    if self.is_cancelled() { Wake::Cancelled } else { Wake::Interrupted }
+                }
+            }
+        }
+
+        // 等待任务被恢复，被取消时返回false
+        pub async fn wait_resume(&self) -> bool {
+            loop {
+                let _waiting = self.waiters.register(&self.timer);
+                let notified = self.notify.notified();
+                tokio::pin!(notified);
+                notified.as_mut().enable();
+                if self.is_cancelled() {
+                    return false;
+                }
+                if !self.is_paused() {
+                    return true;
+                }
+                let _idle = self.timer.idle();
+                notified.await;
+            }
+        }
+
+        pub async fn wait(&self) -> TaskStatus {
+            let mut rx = self.status.subscribe();
+            let r = rx.wait_for(|v| v.is_terminal()).await;
+            match r {
+                Ok(v) => { *v }
+                Err(_) => { self.status() }
+            }
+        }
+    }
+
+    pub(crate) type Registry = Arc<Mutex<HashMap<u64,Arc<TaskControl>>>>;
+
+    // 从注册表中移除这个任务，同ID的新任务不受影响
+    pub(crate) fn unregister(tasks:&Registry,control:&Arc<TaskControl>) {
+        if let Ok(mut tasks) = tasks.lock() {
+            if let Some(v) = tasks.get(&control.id()) {
+                if Arc::ptr_eq(v,control) {
+                    tasks.remove(&control.id());
+                }
+            }
+        }
+    }
+
+    /// handle of a spawned task [任务句柄，用于控制和查询一个已创建的任务]
+    ///
+    /// The handle can be cloned and `.await`ed, awaiting resolves once the
+    /// task is finished or cancelled. Dropping the handle does not stop the task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::{timer, TaskStatus};
+    /// use std::time;
+    ///
+    /// let handle = timer::spawn_ticker(time::Duration::from_millis(10),2,|id| {
+    ///         println!("on ticker:{}",id);
+    ///     }).unwrap();
+    ///
+    /// let status = timer::block_on_rt(async move { handle.await }).unwrap();
+    /// assert_eq!(status,TaskStatus::Finished);
+    /// ```
+    #[derive(Clone)]
+    pub struct TaskHandle {
+        control:Arc<TaskControl>,
+        tasks:Registry,
+    }
+
+    impl TaskHandle {
+        pub(crate) fn new(control:Arc<TaskControl>,tasks:Registry) -> TaskHandle {
+            TaskHandle { control, tasks }
+        }
+
+        /// task id [任务ID]
+        pub fn id(&self) -> u64 {
+            self.control.id()
+        }
+
+        pub(crate) fn control(&self) -> &Arc<TaskControl> {
+            &self.control
+        }
+
+        /// stop the task, a waiting task is woken immediately [停止任务，等待中的任务会被立即唤醒]
+        pub fn cancel(&self) {
+            if self.control.status().is_terminal() {
+                return;
+            }
+            unregister(&self.tasks,&self.control);
+            self.control.cancel();
+        }
+
+        // 已经结束的任务不能再操作
+        fn live(&self) -> TResult<&Arc<TaskControl>> {
+            if self.control.status().is_terminal() {
+                return Err(TError::new(TErrorKind::TaskNotFound));
+            }
+            Ok(&self.control)
+        }
+
+        /// pause the task, fails if the task has ended [暂停任务，任务已结束时返回错误]
+        pub fn pause(&self) -> TResult<()> {
+            self.live()?.pause();
+            Ok(())
+        }
+
+        /// resume a paused task, fails if the task has ended [恢复暂停的任务，任务已结束时返回错误]
+        pub fn resume(&self) -> TResult<()> {
+            self.live()?.resume();
+            Ok(())
+        }
+
+        /// change the interval or date format, keeping id and run count, fails if the task has ended [修改间隔或日期格式，保留ID和执行次数，任务已结束时返回错误]
+        pub fn reschedule(&self,schedule:Schedule) -> TResult<()> {
+            self.live()?.reschedule(schedule)
+        }
+
+        /// snapshot of the task [任务信息快照]
+        pub fn info(&self) -> TaskInfo {
+            self.control.info()
+        }
+
+        /// current status [当前状态]
+        pub fn status(&self) -> TaskStatus {
+            self.control.status()
+        }
+
+        /// next execution time, `None` if paused or ended [下一次执行时间]
+        pub fn next_run(&self) -> Option<DateTime<Local>> {
+            self.control.next_run()
+        }
+
+        /// how many times the task has been executed [已执行的次数]
+        pub fn run_count(&self) -> u64 {
+            self.control.run_count()
+        }
+
+        /// how many runs are left, `None` if not limited by count [剩余的执行次数，不限次数时为None]
+        pub fn runs_left(&self) -> Option<u64> {
+            self.control.runs_left()
+        }
+
+        /// wait until the task is finished or cancelled [等待任务结束]
+        pub async fn wait(&self) -> TaskStatus {
+            self.control.wait().await
+        }
+    }
+
+    impl fmt::Debug for TaskHandle {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_struct("TaskHandle")
+                .field("id",&self.id())
+                .field("status",&self.status())
+                .finish()
+        }
+    }
+
+    impl IntoFuture for TaskHandle {
+        type Output = TaskStatus;
+        type IntoFuture = Pin<Box<dyn Future<Output = TaskStatus> + Send>>;
+
+        fn into_future(self) -> Self::IntoFuture {
+            Box::pin(async move { self.control.wait().await })
+        }
+    }
+
+    /// handle of a one-shot task, awaiting returns the closure's result [单次任务句柄，等待时返回闭包的结果]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::timer;
+    /// use std::time;
+    ///
+    /// let once = timer::spawn_after(time::Duration::from_millis(10),|id| id * 2).unwrap();
+    /// let id = once.id();
+    /// let value = timer::block_on_rt(async move { once.await }).unwrap().unwrap();
+    /// assert_eq!(value,id * 2);
+    /// ```
+    pub struct OnceHandle<T> {
+        handle:TaskHandle,
+        result:oneshot::Receiver<T>,
+    }
+
+    impl<T> OnceHandle<T> {
+        pub(crate) fn new(handle:TaskHandle,result:oneshot::Receiver<T>) -> OnceHandle<T> {
+            OnceHandle { handle, result }
+        }
+
+        /// task id [任务ID]
+        pub fn id(&self) -> u64 {
+            self.handle.id()
+        }
+
+        /// cancel the task if it has not run yet [取消还没有执行的任务]
+        pub fn cancel(&self) {
+            self.handle.cancel();
+        }
+
+        /// the underlying task handle [对应的任务句柄]
+        pub fn handle(&self) -> &TaskHandle {
+            &self.handle
+        }
+
+        /// wait for the result, an error if the task was cancelled or failed [等待执行结果，被取消或执行失败时返回错误]
+        pub async fn result(self) -> TResult<T> {
+            match self.result.await {
+                Ok(v) => { Ok(v) }
+                Err(_) => {
+                    match self.handle.wait().await {
+                        TaskStatus::Cancelled => { Err(TError::new(TErrorKind::Cancelled)) }
+                        _ => {
+                            let err = self.handle.info().last_error.unwrap_or_else(|| String::from("task failed"));
+                            Err(TError::new(TErrorKind::Other(err)))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    impl<T> fmt::Debug for OnceHandle<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_struct("OnceHandle")
+                .field("id",&self.id())
+                .field("status",&self.handle.status())
+                .finish()
+        }
+    }
+
+    impl<T:Send + 'static> IntoFuture for OnceHandle<T> {
+        type Output = TResult<T>;
+        type IntoFuture = Pin<Box<dyn Future<Output = TResult<T>> + Send>>;
+
+        fn into_future(self) -> Self::IntoFuture {
+            Box::pin(self.result())
+        }
+    }
+}
@@ -0,0 +1,256 @@
+#[allow(clippy::module_inception)]
+pub mod testing {
+    use std::sync::{Arc,Mutex,MutexGuard,Condvar};
+    use std::ops::Deref;
+    use std::time::{Duration,Instant};
+    use chrono::{DateTime,Local};
+    use crate::scheduler::scheduler::{Scheduler, Config, TimerBackend};
+    use crate::clock::clock::{Clock, ManualClock};
+    use crate::hooks::hooks::RunHooks;
+
+    pub use crate::hooks::hooks::ExecRecord;
+
+    // 等待任务全部进入等待的最长时间
+    const SETTLE_LIMIT:Duration = Duration::from_secs(10);
+
+    #[derive(Default)]
+    struct RecordState {
+        active:usize, // 活动的任务循环、执行和被唤醒的等待
+        log:Vec<ExecRecord>,
+    }
+
+    // 安装到调度器中，活动减少时通知等待的settle
+    #[derive(Default)]
+    struct Recorder {
+        state:Mutex<RecordState>,
+        changed:Condvar,
+    }
+
+    impl Recorder {
+        fn lock(&self) -> MutexGuard<'_,RecordState> {
+            match self.state.lock() {
+                Ok(v) => { v }
+                Err(e) => { e.into_inner() }
+            }
+        }
+    }
+
+    impl RunHooks for Recorder {
+        fn enter(&self,n:usize) {
+            self.lock().active += n;
+        }
+
+        fn leave(&self,n:usize) {
+            let mut state = self.lock();
+            state.active = state.active.saturating_sub(n);
+            if state.active == 0 {
+                self.changed.notify_all();
+            }
+        }
+
+        fn record(&self,record:ExecRecord) {
+            self.lock().log.push(record);
+        }
+    }
+
+    /// a scheduler on a manual clock that runs due tasks when time is advanced [按手动时钟推进并执行到期任务的测试调度器]
+    ///
+    /// Tasks are spawned through the wrapped `Scheduler`. `advance` and `advance_to`
+    /// move the clock from one due wait to the next and return once every task is
+    /// waiting again, so days of schedules run in milliseconds and in order.
+    /// Bodies that wait on real time still take that long.
+    /// Needs the `testing` feature, the scheduler only reports task activity with it.
+    ///
+    /// # Panics
+    ///
+    /// `advance` and `advance_to` panic when the tasks do not settle within 10 seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use grapeTimerR::testing::testing::TestScheduler;
+    /// use chrono::{Local, TimeZone, Duration};
+    ///
+    /// // 2026-03-02是周一
+    /// let test = TestScheduler::new(Local.ymd(2026,3,2).and_hms(4,0,0));
+    /// let handle = test.spawn_date("Week 1 05:00:00",3,|_| {}).unwrap();
+    ///
+    /// test.advance_to(Local.ymd(2026,3,31).and_hms(0,0,0));
+    /// let runs = test.log().iter().map(|v| v.started_at).collect::<Vec<_>>();
+    /// assert_eq!(runs,(0..3).map(|w| Local.ymd(2026,3,2).and_hms(5,0,0) + Duration::weeks(w)).collect::<Vec<_>>());
+    /// assert!(handle.status().is_terminal());
+    /// ```
+    pub struct TestScheduler {
+        scheduler:Scheduler,
+        clock:ManualClock,
+        recorder:Arc<Recorder>,
+    }
+
+    impl TestScheduler {
+        pub fn new(start:DateTime<Local>) -> TestScheduler {
+            TestScheduler::with_config(start,Config::default())
+        }
+
+        /// use a config, its clock and timer backend are replaced and one worker thread keeps the order
+        /// [使用指定配置，时钟和计时器会被替换，固定一个工作线程以保证顺序]
+        pub fn with_config(start:DateTime<Local>,conf:Config) -> TestScheduler {
+            let clock = ManualClock::new(start);
+            let scheduler = Scheduler::new(Config{
+                thread_count:1,
+                clock:Arc::new(clock.clone()),
+                timer_backend:TimerBackend::Tokio,
+                ..conf
+            });
+            let recorder = Arc::new(Recorder::default());
+            // 新建的调度器锁不会失败
+            let _ = scheduler.set_hooks(recorder.clone());
+            TestScheduler { scheduler, clock, recorder }
+        }
+
+        pub fn scheduler(&self) -> &Scheduler {
+            &self.scheduler
+        }
+
+        pub fn clock(&self) -> &ManualClock {
+            &self.clock
+        }
+
+        pub fn now(&self) -> DateTime<Local> {
+            self.clock.now()
+        }
+
+        /// move the clock forward, running everything due on the way [时钟前进一段时间，依次执行期间到期的任务]
+        pub fn advance(&self,dur:Duration) {
+            let target = self.now() + chrono::Duration::from_std(dur).unwrap_or_else(|_| chrono::Duration::zero());
+            self.advance_to(target);
+        }
+
+        /// move the clock to a time, running everything due until then [时钟前进到指定时间，依次执行期间到期的任务]
+        pub fn advance_to(&self,at:DateTime<Local>) {
+            loop {
+                self.settle();
+                // 每次只唤醒最早的一个等待，同一时间的按创建顺序
+                if !self.clock.step(at) {
+                    break;
+                }
+            }
+            self.clock.set(at);
+            self.settle();
+        }
+
+        /// wait until every task is waiting on the clock, paused or ended [等待所有任务都在等待时钟、暂停或已经结束]
+        ///
+        /// Task loops and runs report when they start and stop waiting, and a wait woken by
+        /// the clock or by a handle counts as active until its task continues, so this
+        /// returns exactly when nothing can run without the clock moving.
+        pub fn settle(&self) {
+            let begin = Instant::now();
+            let mut state = self.recorder.lock();
+            while state.active > 0 || self.clock.woken() > 0 {
+                let left = match SETTLE_LIMIT.checked_sub(begin.elapsed()) {
+                    Some(v) => { v }
+                    None => { panic!("tasks did not settle within {:?}",SETTLE_LIMIT); }
+                };
+                state = match self.recorder.changed.wait_timeout(state,left) {
+                    Ok(v) => { v.0 }
+                    Err(e) => { e.into_inner().0 }
+                };
+            }
+        }
+
+        /// executions recorded so far [目前记录的执行]
+        pub fn log(&self) -> Vec<ExecRecord> {
+            self.recorder.lock().log.clone()
+        }
+
+        /// take the recorded executions and start a new log [取出记录的执行并清空]
+        pub fn take_log(&self) -> Vec<ExecRecord> {
+            std::mem::take(&mut self.recorder.lock().log)
+        }
+
+        /// executions of one task [某个任务的执行记录]
+        pub fn runs_of(&self,id:u64) -> Vec<ExecRecord> {
+            self.log().into_iter().filter(|v| v.id == id).collect()
+        }
+    }
+
+    impl Deref for TestScheduler {
+        type Target = Scheduler;
+
+        fn deref(&self) -> &Scheduler {
+            &self.scheduler
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::testing::TestScheduler;
+    use crate::schedule::schedule::{Schedule, TaskOptions, RunLimit, RetryPolicy, Backoff};
+    use crate::task::task::TaskStatus;
+    use chrono::{Local, TimeZone, Duration};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64,Ordering};
+    use std::time;
+
+    #[test]
+    fn test_week_job() {
+        // 2026-03-02是周一
+        let test = TestScheduler::new(Local.ymd(2026,3,2).and_hms(4,0,0));
+        let handle = test.spawn_date("Week 1 05:00:00",0,|_| {}).unwrap();
+
+        test.advance(time::Duration::from_secs(3600 * 24 * 28));
+        let runs = test.runs_of(handle.id());
+        assert_eq!(runs.len(),4);
+        for (i,run) in runs.iter().enumerate() {
+            let at = Local.ymd(2026,3,2).and_hms(5,0,0) + Duration::weeks(i as i64);
+            assert_eq!(run.run_index,i as u64 + 1);
+            assert_eq!(run.scheduled_at,at);
+            assert_eq!(run.started_at,at);
+            assert!(run.result.is_ok());
+        }
+        assert_eq!(handle.next_run(),Some(Local.ymd(2026,3,30).and_hms(5,0,0)));
+    }
+
+    #[test]
+    fn test_run_limit_days() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+        let daily = test.spawn_date("Day 05:00:00",30,|_| {}).unwrap();
+        let until = test.spawn_with(Schedule::Tick(time::Duration::from_secs(3600 * 24)),RunLimit::Until(start + Duration::days(10)),TaskOptions::default(),|_| {}).unwrap();
+
+        test.advance(time::Duration::from_secs(3600 * 24 * 40));
+        let runs = test.runs_of(daily.id());
+        assert_eq!(runs.len(),30);
+        assert_eq!(runs.last().unwrap().started_at,Local.ymd(2026,3,31).and_hms(5,0,0));
+        assert_eq!(daily.status(),TaskStatus::Finished);
+
+        // 截止时间当时仍会执行，之后不再执行
+        let runs = test.runs_of(until.id());
+        assert_eq!(runs.len(),10);
+        assert_eq!(runs.last().unwrap().started_at,start + Duration::days(10));
+        assert_eq!(until.status(),TaskStatus::Finished);
+        assert_eq!(test.now(),start + Duration::days(40));
+    }
+
+    #[test]
+    fn test_order_and_retry() {
+        let test = TestScheduler::new(Local.ymd(2026,3,2).and_hms(0,0,0));
+        let slow = test.spawn_ticker(time::Duration::from_secs(3),2,|_| {}).unwrap();
+        let fast = test.spawn_ticker(time::Duration::from_secs(2),3,|_| {}).unwrap();
+
+        // 失败一次后按重试策略在测试时钟上重试
+        let calls = Arc::new(AtomicU64::new(0));
+        let c = calls.clone();
+        let opts = TaskOptions{ retry:Some(RetryPolicy{ max_attempts:2, backoff:Backoff::Fixed(time::Duration::from_secs(1)), jitter:time::Duration::from_secs(0) }), ..TaskOptions::default() };
+        let flaky = test.spawn_fallible(Schedule::Tick(time::Duration::from_secs(10)),1,opts,move |_| {
+            if c.fetch_add(1,Ordering::SeqCst) == 0 { Err("first call fails") } else { Ok(()) }
+        }).unwrap();
+
+        test.advance(time::Duration::from_secs(20));
+        let order = test.take_log().iter().map(|v| (v.id,v.started_at.timestamp() % 60)).collect::<Vec<_>>();
+        assert_eq!(order,vec![(fast.id(),2),(slow.id(),3),(fast.id(),4),(slow.id(),6),(fast.id(),6),(flaky.id(),10)]);
+        assert_eq!(calls.load(Ordering::SeqCst),2);
+        assert!(test.log().is_empty());
+    }
+}
@@ -1,66 +1,716 @@
-
 pub mod threads {
     use log::*;
     use tokio::{runtime,time};
-    use crossbeam::channel::{unbounded,Receiver,Sender};
-    use std::{ thread,sync::Mutex,sync::Once,time::Duration,
-               sync::atomic::AtomicI8, sync::atomic::Ordering,
-               sync::Arc };
-    use chrono::{Local};
-    use crate::schedule::schedule::{TaskAction};
+    use std::sync::{Arc,Mutex};
+    use std::collections::HashMap;
+    use chrono::{DateTime, Local};
+    use crate::schedule::schedule::{TaskAction, AsyncTaskAction, Schedule, TaskDef, TaskBody, RetryPolicy, FailurePolicy, OverlapPolicy, TickMode, Jitter};
+    use crate::task::task::{TaskControl, TaskHandle, TaskInfo, Registry, Wake, unregister};
+    use crate::context::context::ExecContext;
+    use crate::wheel::wheel::{Timer, TimingWheel, WheelKey};
+    #[cfg(any(test, feature = "testing"))]
+    use crate::hooks::hooks::{Hooks, ExecRecord};
     use crate::errors::errors::{TError, TResult, TErrorKind};
-    use std::borrow::{BorrowMut, Borrow};
-    use std::ops::Add;
-    use crate::uuid::uuid::next_big_id;
-    use crate::parsers::parsers::parser_timestamp;
-    use std::sync::mpsc::channel;
+    use crate::parsers::parsers::parser_next_with;
+    use crate::clock::clock::Clock;
+    use crate::scheduler::scheduler::Config;
     use std::future::Future;
+    use std::task::Waker;
     use tokio::task::JoinHandle;
+    use tokio::sync::{Semaphore, oneshot};
+    use crate::hooks::hooks::Busy;
+
+    // 日期任务到下一次执行的等待时间，精确到毫秒，避免提前唤醒后重复执行
+    fn date_wait(clock:&dyn Clock,date_format:&str) -> TResult<time::Duration> {
+        let next_time = parser_next_with(date_format,clock)?;
+        let wait = (next_time - clock.now()).num_microseconds().unwrap_or(0).max(0) as u64;
+        Ok(time::Duration::from_millis(wait.div_ceil(1000)))
+    }
+
+    // 时钟的单调时间
+    fn instant_now(clock:&dyn Clock) -> time::Instant {
+        time::Instant::from_std(clock.instant())
+    }
+
+    // 一次执行在调度中的位置，用于生成执行上下文
+    struct RunSlot {
+        handle:TaskHandle,
+        index:u64,
+        scheduled_at:DateTime<Local>,
+    }
+
+    // 执行一次，失败时按重试策略在下一次正常执行前重试
+    async fn execute_with_retry(task:&TaskBody,slot:&RunSlot,retry:Option<RetryPolicy>,timeout:Option<time::Duration>) -> Result<(),String> {
+        let control = slot.handle.control();
+        let mut attempt = 0;
+        loop {
+            // 每次尝试使用新的上下文，超时的取消标记不影响重试
+            let ctx = ExecContext::new(slot.handle.clone(),slot.index,slot.scheduled_at);
+            let err = match task.run_isolated(ctx,timeout).await {
+                Ok(_) => { return Ok(()); }
+                Err(e) => { e }
+            };
+
+            let policy = match retry {
+                Some(v) if attempt < v.max_attempts => { v }
+                _ => { return Err(err); }
+            };
+            attempt += 1;
+
+            // 重试不能晚于下一次正常执行
+            let delay = policy.delay_with_jitter(attempt);
+            let next_slot = match control.schedule() {
+                Schedule::Tick(tick) => { Ok(tick) }
+                Schedule::Date(date_format) => { date_wait(&**control.clock(),&date_format) }
+            };
+            if let Ok(next) = next_slot {
+                if delay >= next {
+                    return Err(err);
+                }
+            }
+
+            warn!("task retry id:{} attempt:{} delay ms:{} err:{}",control.id(),attempt,delay.as_millis(),err);
+            control.record_error(err.clone());
+            match control.sleep(delay).await {
+                Wake::Cancelled => { return Err(err); }
+                Wake::Interrupted => {
+                    if !control.wait_resume().await {
+                        return Err(err);
+                    }
+                }
+                Wake::Elapsed => {}
+            }
+        }
+    }
+
+    // 单次执行使用的策略
+    #[derive(Clone, Copy)]
+    struct RunPolicy {
+        failure_policy:FailurePolicy,
+        retry:Option<RetryPolicy>,
+        timeout:Option<time::Duration>,
+    }
+
+    // 执行一次并记录结果，失败策略要求停止时返回false
+    async fn run_once(task:&TaskBody,slot:RunSlot,policy:RunPolicy) -> bool {
+        let control = slot.handle.control();
+        control.begin_run();
+        #[cfg(any(test, feature = "testing"))]
+        let (index,scheduled_at,started_at) = (slot.index,slot.scheduled_at,control.clock().now());
+        let result = execute_with_retry(task,&slot,policy.retry,policy.timeout).await;
+        #[cfg(any(test, feature = "testing"))]
+        if let Some(hooks) = control.timer().hooks() {
+            hooks.record(ExecRecord{
+                id:control.id(),
+                run_index:index,
+                scheduled_at,
+                started_at,
+                result:result.as_ref().map(|_| ()).map_err(|e| e.to_string()),
+            });
+        }
+        let alive = match result {
+            Ok(_) => {
+                control.record_success();
+                true
+            }
+            Err(e) => {
+                error!("task execute failed id:{} err:{}",control.id(),e);
+                let failures = control.record_failure(e);
+                if policy.failure_policy.should_stop(failures) {
+                    control.fail();
+                    false
+                }else {
+                    true
+                }
+            }
+        };
+        control.end_run();
+        alive
+    }
+
+    // 非串行模式的执行许可：同时存在的执行数量，以及是否需要依次执行
+    fn overlap_slots(overlap:OverlapPolicy) -> Option<(usize,bool)> {
+        match overlap {
+            OverlapPolicy::Serial => { None }
+            OverlapPolicy::SkipIfRunning => { Some((1,false)) }
+            OverlapPolicy::Queue(n) => { Some((n + 1,true)) }
+            OverlapPolicy::Concurrent(max) => { Some((max.max(1),false)) }
+        }
+    }
+
+    // 非串行模式下还没有结束的执行，最后一个结束时把活动交给等待的任务循环，测试调度器不会在交接时认为已经空闲
+    #[derive(Default)]
+    struct Outstanding {
+        state:Mutex<(usize,Option<oneshot::Sender<Busy>>)>,
+    }
+
+    impl Outstanding {
+        fn lock(&self) -> std::sync::MutexGuard<'_,(usize,Option<oneshot::Sender<Busy>>)> {
+            match self.state.lock() {
+                Ok(v) => { v }
+                Err(e) => { e.into_inner() }
+            }
+        }
+
+        fn add(&self) {
+            self.lock().0 += 1;
+        }
+
+        fn done(&self,timer:&Timer) {
+            let mut state = self.lock();
+            state.0 = state.0.saturating_sub(1);
+            if state.0 == 0 {
+                if let Some(tx) = state.1.take() {
+                    let _ = tx.send(timer.busy());
+                }
+            }
+        }
+
+        // 等待所有执行结束
+        async fn wait(&self,timer:&Timer) {
+            let rx = {
+                let mut state = self.lock();
+                if state.0 == 0 {
+                    return;
+                }
+                let (tx,rx) = oneshot::channel();
+                state.1 = Some(tx);
+                rx
+            };
+            // 先恢复自己的活动再释放交来的活动
+            let _busy = {
+                let _idle = timer.idle();
+                rx.await
+            };
+        }
+    }
+
+    // 任务循环下一步要做的事
+    enum Plan {
+        Wait(time::Duration), // 等待一段时间后执行
+        Paused,               // 暂停中，等待恢复
+        Drain,                // 正常结束，等待还在执行的任务
+        Stop,                 // 已经结束或计划异常
+    }
+
+    // 等待到期之后要做的事
+    enum Fire {
+        Run(DateTime<Local>), // 按计划的时间执行
+        Again,                // 还没有到真正的开始，重新计算等待
+        Drain,                // 已经超过结束时间
+    }
+
+    // 任务的计时状态，任务循环和时间轮驱动共用
+    struct Pacer {
+        task_id:u64,
+        debug:bool,
+        clock:Arc<dyn Clock>,
+        tick_mode:TickMode,
+        jitter:Jitter,
+        start_at:Option<DateTime<Local>>,
+        run_at_start:bool,
+        end_at:Option<DateTime<Local>>,
+        // 间隔任务被暂停时剩余的等待时间
+        remaining:Option<time::Duration>,
+        // 固定频率下一次应执行的时间，以及是否已经执行过
+        due:Option<time::Instant>,
+        fired:bool,
+        // 间隔任务是否已经按固定分散移动过
+        shifted:bool,
+        // 开始的时间，间隔任务在开始时执行第一次，日期任务从开始后的下一个日期执行
+        first:Option<time::Instant>,
+        // 这一次等待的计划
+        is_ticker:bool,
+        next_run:Option<DateTime<Local>>,
+        deadline:Option<time::Instant>,
+    }
+
+    impl Pacer {
+        // 计算下一次等待，暂停时保留间隔任务剩余的时间
+        fn plan(&mut self,control:&TaskControl) -> Plan {
+            if control.status().is_terminal() {
+                return Plan::Stop;
+            }
+            // 最后一次执行后立即结束
+            if control.run_limit().is_exhausted(control.run_count()) {
+                return Plan::Drain;
+            }
+            let schedule = control.schedule();
+            if control.take_rescheduled() {
+                self.remaining = None;
+                self.due = None;
+                self.shifted = false;
+            }
+
+            self.is_ticker = schedule.is_tick();
+            let wait = match self.wait_for(&schedule) {
+                Some(v) => { v }
+                None => { return Plan::Stop; }
+            };
+
+            if control.is_paused() {
+                // 间隔任务保留剩余时间，日期任务恢复后重新计算
+                if self.is_ticker {
+                    self.remaining = Some(wait);
+                }
+                control.set_next_run(None);
+                return Plan::Paused;
+            }
+
+            let next_run = chrono::Duration::from_std(wait).ok().map(|v| self.clock.now() + v);
+            // 下一次执行已经超过结束时间
+            if let (Some(end),Some(next)) = (self.end_at,next_run) {
+                if next > end {
+                    return Plan::Drain;
+                }
+            }
+            control.set_next_run(next_run);
+            self.next_run = next_run;
+            self.deadline = Some(instant_now(&*self.clock) + wait);
+            Plan::Wait(wait)
+        }
+
+        // 到下一次执行的等待，计划异常时返回None
+        fn wait_for(&mut self,schedule:&Schedule) -> Option<time::Duration> {
+            let (task_id,debug) = (self.task_id,self.debug);
+            // 先暂停
+            if let Some(at) = self.first {
+                if self.is_ticker && self.tick_mode != TickMode::FixedDelay {
+                    self.due = Some(at);
+                }
+                return Some(at.saturating_duration_since(instant_now(&*self.clock)));
+            }
+            match schedule {
+                Schedule::Date(date_format) => {
+                    let wait = match date_wait(&*self.clock,date_format) {
+                        Ok(v) => { v }
+                        Err(e) => {
+                            error!("bad date format:{} id:{} err:{}",date_format,task_id,e);
+                            return None;
+                        }
+                    };
+
+                    let wait = wait + self.jitter.delay(wait);
+                    if debug {
+                        debug!("make next tick sec:{} id:{}",wait.as_secs(),task_id);
+                    }
+
+                    Some(wait)
+                }
+                Schedule::Tick(tick) => {
+                    if tick.as_millis() == 0 {
+                        return None; // 异常的任务
+                    }
+
+                    // 固定分散只在第一次等待时加上一次，之后的间隔不变
+                    let spread = if self.shifted { time::Duration::ZERO } else { self.jitter.spread() };
+                    self.shifted = true;
+                    let wait = match self.tick_mode {
+                        // 暂停后剩余的时间已经包含了这一次的额外等待
+                        TickMode::FixedDelay => { self.remaining.take().unwrap_or_else(|| *tick + spread + self.jitter.random(*tick)) }
+                        TickMode::FixedRate(missed) => {
+                            let now = instant_now(&*self.clock);
+                            // 暂停或打断后按剩余时间重新锚定，暂停期间错过的间隔不补执行
+                            let next = match (self.remaining.take(),self.due) {
+                                (Some(left),_) => { now + left }
+                                (None,Some(last)) if self.fired => { missed.next_due(last,*tick,now) }
+                                (None,Some(v)) => { v }
+                                (None,None) => { now + *tick }
+                            };
+                            // 固定分散移动锚点，随机的额外等待不改变锚点
+                            let next = next + spread;
+                            self.fired = false;
+                            self.due = Some(next);
+                            next.saturating_duration_since(now) + self.jitter.random(*tick)
+                        }
+                    };
+                    if debug {
+                        debug!("make next ticker ms:{} id:{}",wait.as_millis(),task_id);
+                    }
+
+                    Some(wait)
+                }
+            }
+        }
+
+        // 等待被状态变化打断，间隔任务保留剩余的时间
+        fn interrupted(&mut self) {
+            if let (true,Some(deadline)) = (self.is_ticker,self.deadline) {
+                self.remaining = Some(deadline.saturating_duration_since(instant_now(&*self.clock)));
+            }
+        }
+
+        // 等待到期
+        fn elapsed(&mut self) -> Fire {
+            self.fired = true;
+            if self.first.is_some() {
+                // 等待期间时钟可能被调整，按时钟重新计算到开始时间的等待
+                if let Some(left) = self.start_at.and_then(|v| (v - self.clock.now()).to_std().ok()).filter(|v| !v.is_zero()) {
+                    self.first = Some(instant_now(&*self.clock) + left);
+                    return Fire::Again;
+                }
+            }
+            if self.first.take().is_some() {
+                self.remaining = None;
+                // 日期任务到开始时间后再计算下一个日期，除非要求开始时执行一次
+                if !self.is_ticker && !self.run_at_start {
+                    return Fire::Again;
+                }
+            }
+
+            if self.end_at.is_some_and(|v| self.clock.now() > v) {
+                return Fire::Drain;
+            }
+            Fire::Run(self.next_run.unwrap_or_else(|| self.clock.now()))
+        }
+    }
+
+    // 任务结束，先从注册表中移除，等待结束的一方唤醒后查不到这个任务
+    fn end_task(control:&Arc<TaskControl>,registry:&Registry,drain:bool,debug:bool) {
+        if drain && debug {
+            debug!("task finished:{}",control.id());
+        }
+        if control.is_cancelled() && debug {
+            debug!("task stopped:{}",control.id());
+        }
+        control.set_on_change(None);
+        unregister(registry,control);
+        control.finish();
+    }
+
+    // 时间轮驱动的任务从哪里继续
+    enum Resume {
+        Plan,        // 开始或者恢复，计算下一次等待
+        Fired,       // 时间轮上的等待到期
+        Interrupted, // 等待被状态变化收回
+    }
+
+    // 登记等待的结果
+    enum Arm {
+        Waiting, // 已经登记，由时间轮或状态变化继续
+        Due,     // 已经到期
+        Changed, // 登记之前状态已经变化
+    }
+
+    #[derive(Default)]
+    struct DriveState {
+        epoch:u64, // 每次登记加一，过期的唤醒不再继续
+        armed:Option<WheelKey>,
+        parked:bool, // 暂停中，恢复时继续
+    }
+
+    // 时间轮驱动的串行任务：等待期间没有tokio任务，只在时间轮上有一个登记
+    // 到期或状态变化时在运行时中执行一段：执行一次，计算下一次等待，重新登记
+    struct Driven {
+        control:Arc<TaskControl>,
+        handle:TaskHandle,
+        task:TaskBody,
+        policy:RunPolicy,
+        registry:Registry,
+        debug:bool,
+        rt:runtime::Handle,
+        wheel:Arc<TimingWheel>,
+        pacer:Mutex<Pacer>, // 同一时间只有一段在执行
+        state:Mutex<DriveState>,
+    }
+
+    // 时间轮到期时的唤醒
+    struct FireWake {
+        driven:Arc<Driven>,
+        epoch:u64,
+    }
+
+    impl std::task::Wake for FireWake {
+        fn wake(self:Arc<Self>) {
+            self.driven.fired(self.epoch);
+        }
+    }
+
+    fn lock<T>(v:&Mutex<T>) -> std::sync::MutexGuard<'_,T> {
+        match v.lock() {
+            Ok(v) => { v }
+            Err(e) => { e.into_inner() }
+        }
+    }
+
+    impl Driven {
+        fn spawn_step(self:&Arc<Self>,resume:Resume) {
+            let driven = self.clone();
+            self.rt.spawn(async move { driven.step(resume).await });
+        }
+
+        fn fired(self:&Arc<Self>,epoch:u64) {
+            let mut state = lock(&self.state);
+            if state.epoch != epoch || state.armed.take().is_none() {
+                return;
+            }
+            drop(state);
+            self.spawn_step(Resume::Fired);
+        }
+
+        // 状态变化：收回时间轮上的等待，或者从暂停中继续，执行中的一段之后会自己检查状态
+        fn interrupt(self:&Arc<Self>) {
+            let mut state = lock(&self.state);
+            let resume = if let Some(key) = state.armed {
+                // 已经到期的由时间轮的唤醒继续
+                if !self.wheel.cancel(key) {
+                    return;
+                }
+                state.armed = None;
+                state.epoch += 1;
+                Resume::Interrupted
+            }else if std::mem::take(&mut state.parked) {
+                Resume::Plan
+            }else {
+                return;
+            };
+            drop(state);
+            self.spawn_step(resume);
+        }
+
+        // 先登记再检查状态，检查之后的变化由回调收回登记
+        fn arm(self:&Arc<Self>,deadline:time::Instant,seen:u64) -> Arm {
+            if self.control.changes() != seen {
+                return Arm::Changed;
+            }
+            let mut state = lock(&self.state);
+            state.epoch += 1;
+            let waker = Waker::from(Arc::new(FireWake{ driven:self.clone(), epoch:state.epoch }));
+            match self.wheel.schedule(deadline.into_std(),waker) {
+                Some(key) => { state.armed = Some(key); }
+                None => { return Arm::Due; }
+            }
+            drop(state);
+            if self.control.changes() == seen {
+                return Arm::Waiting;
+            }
+            let mut state = lock(&self.state);
+            match state.armed {
+                Some(key) if self.wheel.cancel(key) => {
+                    state.armed = None;
+                    state.epoch += 1;
+                    Arm::Changed
+                }
+                // 已经被回调收回或者已经到期
+                _ => { Arm::Waiting }
+            }
+        }
+
+        async fn step(self:Arc<Self>,mut resume:Resume) {
+            let control = &self.control;
+            let mut drain = false;
+            loop {
+                match resume {
+                    Resume::Fired => {
+                        if control.status().is_terminal() {
+                            break;
+                        }
+                        let fire = lock(&self.pacer).elapsed();
+                        match fire {
+                            Fire::Again => {}
+                            Fire::Drain => {
+                                drain = true;
+                                break;
+                            }
+                            Fire::Run(scheduled_at) => {
+                                let r_count = control.add_run();//计数
+                                if self.debug {
+                                    debug!("task run count:{} id:{}",r_count,control.id());
+                                }
+                                let slot = RunSlot { handle:self.handle.clone(), index:r_count, scheduled_at };
+                                if !run_once(&self.task,slot,self.policy).await {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Resume::Interrupted => { lock(&self.pacer).interrupted(); }
+                    Resume::Plan => {}
+                }
+
+                let seen = control.changes();
+                let plan = lock(&self.pacer).plan(control);
+                resume = match plan {
+                    Plan::Stop => { break; }
+                    Plan::Drain => {
+                        drain = true;
+                        break;
+                    }
+                    Plan::Paused => {
+                        lock(&self.state).parked = true;
+                        // 登记之后再检查，之后的恢复和停止由回调继续
+                        if control.is_paused() && !control.is_cancelled() {
+                            return;
+                        }
+                        if !std::mem::take(&mut lock(&self.state).parked) {
+                            return;
+                        }
+                        Resume::Plan
+                    }
+                    Plan::Wait(_) => {
+                        let deadline = match lock(&self.pacer).deadline {
+                            Some(v) => { v }
+                            None => { break; }
+                        };
+                        match self.arm(deadline,seen) {
+                            Arm::Waiting => { return; }
+                            Arm::Due => { Resume::Fired }
+                            Arm::Changed => { Resume::Interrupted }
+                        }
+                    }
+                };
+            }
+            end_task(control,&self.registry,drain,self.debug);
+        }
+    }
+
+    fn build_runtime(conf:&Config) -> runtime::Runtime {
+        runtime::Builder::new_multi_thread()
+            .worker_threads(conf.thread_count as usize)
+            .max_blocking_threads(conf.blocking_thread_count.max(1) as usize)
+            .enable_all()
+            .build()
+            .unwrap()
+    }
 
     pub struct TaskPool {
         rt:tokio::runtime::Runtime,
-        stop_tx:Sender<u64>,
-        stop_rx:Receiver<u64>,
+        tasks:Registry,
         debug:bool,
+        default_timeout:Option<time::Duration>,
+        timer:Timer,
     }
 
-    impl TaskPool {
-        pub fn new(tick:Duration,count:i32) -> TaskPool {
-            let (tx,rx) =unbounded();
+    // 时间轮驱动的任务和它的控制句柄互相持有，释放时断开
+    impl Drop for TaskPool {
+        fn drop(&mut self) {
+            if let Ok(tasks) = self.tasks.lock() {
+                tasks.values().for_each(|v| v.set_on_change(None));
+            }
+        }
+    }
 
+    impl TaskPool {
+        pub fn new(conf:&Config) -> TaskPool {
             TaskPool {
-                rt: runtime::Builder::new_multi_thread()
-                    .worker_threads(count as usize)
-                    .enable_all()
-                    .build()
-                    .unwrap(),
-                stop_tx: tx,
-                stop_rx: rx,
-                debug:false,
+                rt: build_runtime(conf),
+                tasks: Arc::new(Mutex::new(HashMap::new())),
+                debug: conf.debug,
+                default_timeout: conf.default_timeout,
+                timer: Timer::new(conf.timer_backend,conf.clock.clone()),
+            }
+        }
+
+        pub fn rebuild(&mut self,conf:&Config) {
+            // 旧运行时中的任务会随运行时一起结束
+            if let Ok(mut tasks) = self.tasks.lock() {
+                for (_,control) in tasks.drain() {
+                    control.cancel();
+                    control.set_on_change(None);
+                }
             }
+
+            self.debug = conf.debug;
+            self.default_timeout = conf.default_timeout;
+            self.timer = self.timer.renew(conf.timer_backend,conf.clock.clone());
+            self.rt = build_runtime(conf);
         }
 
-        pub fn rebuild(&mut self,count:i32,debug:bool) {
-            self.debug = debug;
-            self.rt = runtime::Builder::new_multi_thread()
-                .worker_threads(count as usize)
-                .enable_all()
-                .build()
-                .unwrap();
+        pub fn stop_task(&self,id:u64) -> TResult<()> {
+            let mut tasks = self.tasks.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            match tasks.remove(&id) {
+                None => { Err(TError::new(TErrorKind::TaskNotFound)) }
+                Some(control) => {
+                    control.cancel();
+                    Ok(())
+                }
+            }
         }
 
-        pub fn stop_task(&mut self,id:u64) -> TResult<()> {
-            match self.stop_tx.try_send(id) {
-                Err(e) => { Err(TError::new(TErrorKind::Other(e.to_string()))) }
-                Ok(_) => { Ok(()) }
+        fn find_task(&self,id:u64) -> TResult<Arc<TaskControl>> {
+            let tasks = self.tasks.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            match tasks.get(&id) {
+                None => { Err(TError::new(TErrorKind::TaskNotFound)) }
+                Some(control) => { Ok(control.clone()) }
             }
         }
 
-        pub fn block_on<F>(&self,future: F) -> F::Output
-            where
-                F: Future,
-        {
-            self.rt.block_on(future)
+        pub fn pause_task(&self,id:u64) -> TResult<()> {
+            self.find_task(id)?.pause();
+            Ok(())
+        }
+
+        pub fn resume_task(&self,id:u64) -> TResult<()> {
+            self.find_task(id)?.resume();
+            Ok(())
+        }
+
+        pub fn reschedule_task(&self,id:u64,schedule:Schedule) -> TResult<()> {
+            self.find_task(id)?.reschedule(schedule)
+        }
+
+        pub fn task_info(&self,id:u64) -> TResult<TaskInfo> {
+            Ok(self.find_task(id)?.info())
+        }
+
+        pub fn list_tasks(&self) -> TResult<Vec<TaskInfo>> {
+            let tasks = self.tasks.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            let mut infos = tasks.values().map(|v| v.info()).collect::<Vec<TaskInfo>>();
+            infos.sort_by_key(|v| v.id);
+            Ok(infos)
+        }
+
+        fn find_group(&self,tag:&str) -> TResult<Vec<Arc<TaskControl>>> {
+            let tasks = self.tasks.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            Ok(tasks.values().filter(|v| v.has_tag(tag)).cloned().collect())
+        }
+
+        // 停止分组内的所有任务，返回停止的数量
+        pub fn stop_group(&self,tag:&str) -> TResult<usize> {
+            let mut tasks = self.tasks.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+            let ids = tasks.values().filter(|v| v.has_tag(tag)).map(|v| v.id()).collect::<Vec<u64>>();
+            for id in ids.iter() {
+                if let Some(control) = tasks.remove(id) {
+                    control.cancel();
+                }
+            }
+            Ok(ids.len())
+        }
+
+        pub fn pause_group(&self,tag:&str) -> TResult<usize> {
+            let group = self.find_group(tag)?;
+            group.iter().for_each(|v| v.pause());
+            Ok(group.len())
+        }
+
+        pub fn resume_group(&self,tag:&str) -> TResult<usize> {
+            let group = self.find_group(tag)?;
+            group.iter().for_each(|v| v.resume());
+            Ok(group.len())
+        }
+
+        pub fn list_group(&self,tag:&str) -> TResult<Vec<TaskInfo>> {
+            let mut infos = self.find_group(tag)?.iter().map(|v| v.info()).collect::<Vec<TaskInfo>>();
+            infos.sort_by_key(|v| v.id);
+            Ok(infos)
+        }
+
+        pub fn rt_handle(&self) -> runtime::Handle {
+            self.rt.handle().clone()
+        }
+
+        pub fn clock(&self) -> Arc<dyn Clock> {
+            self.timer.clock().clone()
+        }
+
+        pub(crate) fn timer(&self) -> Timer {
+            self.timer.clone()
+        }
+
+        // 之后创建的任务向观察者报告活动和执行
+        #[cfg(any(test, feature = "testing"))]
+        pub(crate) fn set_hooks(&mut self,hooks:Hooks) {
+            self.timer = self.timer.clone().with_hooks(Some(hooks));
         }
 
         pub fn spawn_rt<F>(&self,future: F) -> JoinHandle<F::Output>
@@ -71,65 +721,1050 @@ pub mod threads {
             self.rt.spawn(future)
         }
 
-        pub fn spawn(&self, t:Arc<dyn TaskAction>) {
-            let task = t.clone();
-            let rx_spwan = self.stop_rx.clone();
+        pub fn spawn(&self, t:Arc<dyn TaskAction>) -> TResult<TaskHandle> {
+            let def = TaskDef::from_action(&*t);
+            let body = TaskBody::sync(t,def.options.exec_mode)?;
+            self.spawn_task(def,body)
+        }
+
+        pub fn spawn_async(&self, t:Arc<dyn AsyncTaskAction>) -> TResult<TaskHandle> {
+            self.spawn_task(TaskDef::from_async_action(&*t),TaskBody::Async(t))
+        }
+
+        fn spawn_task(&self, def:TaskDef, task:TaskBody) -> TResult<TaskHandle> {
+            def.schedule.validate()?;
+            let task_id = def.id;
+            let policy = RunPolicy {
+                failure_policy:def.options.failure_policy,
+                retry:def.options.retry,
+                timeout:def.options.timeout.or(self.default_timeout),
+            };
+            let slots = overlap_slots(def.options.overlap);
+            // 开始前的等待，取开始时间和初始延迟中较晚的一个
+            let clock = self.timer.clock().clone();
+            let start_at = def.options.start_at;
+            let run_at_start = def.options.run_at_start;
+            let start_wait = match (start_at.and_then(|v| (v - clock.now()).to_std().ok()),def.options.initial_delay) {
+                (None,None) if !run_at_start => { None }
+                (at,delay) => { Some(at.unwrap_or_default().max(delay.unwrap_or_default())) }
+            };
+            // 结束时间和执行截止时间取较早的一个
+            let end_at = match (def.options.end_at,def.run_limit.until()) {
+                (Some(a),Some(b)) => { Some(a.min(b)) }
+                (a,b) => { a.or(b) }
+            };
+            let control = Arc::new(TaskControl::new(task_id,def.schedule,def.run_limit,def.options.tags).with_timer(self.timer.clone()));
+            {
+                let mut tasks = self.tasks.lock().map_err(|_| TError::new(TErrorKind::MutexError))?;
+                if tasks.contains_key(&task_id) {
+                    return Err(TError::new(TErrorKind::TaskExists));
+                }
+                tasks.insert(task_id,control.clone());
+            }
+
+            let registry = self.tasks.clone();
+            let handle = TaskHandle::new(control.clone(),self.tasks.clone());
+            let task_handle = handle.clone();
             let debug = self.debug;
+            let mut pacer = Pacer {
+                task_id,
+                debug,
+                first:start_wait.map(|v| instant_now(&*clock) + v),
+                clock,
+                tick_mode:def.options.tick_mode,
+                jitter:def.options.jitter,
+                start_at,
+                run_at_start,
+                end_at,
+                remaining:None,
+                due:None,
+                fired:false,
+                shifted:false,
+                is_ticker:false,
+                next_run:None,
+                deadline:None,
+            };
+
+            // 串行任务使用时间轮时由时间轮驱动，等待期间没有任务循环
+            if let (None,Some(wheel)) = (slots,self.timer.wheel()) {
+                let driven = Arc::new(Driven {
+                    control:control.clone(),
+                    handle:task_handle,
+                    task,
+                    policy,
+                    registry,
+                    debug,
+                    rt:self.rt.handle().clone(),
+                    wheel:wheel.clone(),
+                    pacer:Mutex::new(pacer),
+                    state:Mutex::new(DriveState::default()),
+                });
+                // 暂停期间只有回调持有驱动，任务结束或任务池释放时断开
+                let on_change = driven.clone();
+                control.set_on_change(Some(Arc::new(move || on_change.interrupt())));
+                driven.spawn_step(Resume::Plan);
+                return Ok(handle);
+            }
+
+            // 在启动前计为活动，避免测试调度器在任务开始前认为已经空闲
+            let busy = self.timer.busy();
 
             self.rt.spawn(async move {
-                let mut r_count = 0;
-                let max_count = task.loop_count();
+                let _busy = busy;
+                // 非串行模式下，admitted限制同时存在的执行，排队的执行依次等待上一次执行交出的轮次
+                let admitted = slots.map(|(n,_)| Arc::new(Semaphore::new(n)));
+                let outstanding = slots.map(|_| Arc::new(Outstanding::default()));
+                let ordered = matches!(slots,Some((_,true)));
+                let mut turn:Option<oneshot::Receiver<Busy>> = None;
+                // 正常结束时需要等待还在执行的任务
+                let mut drain = false;
                 loop {
-                    // 先暂停
-                    if task.date_format().len() > 0 {
-                        let now_time = Local::now().timestamp();
-                        let next_tick = parser_timestamp(task.date_format()).unwrap();
-
-                        if debug {
-                            debug!("make next tick sec:{} id:{}",(next_tick - now_time) as u64,task.id());
+                    // 这一轮检查状态之后的变化会让等待立即返回
+                    let seen = control.changes();
+                    let wait = match pacer.plan(&control) {
+                        Plan::Stop => { break; }
+                        Plan::Drain => {
+                            drain = true;
+                            break;
                         }
-
-                        // 等待一下，让出这个线程
-                        time::sleep(time::Duration::from_secs( (next_tick - now_time) as u64 )).await;
-                    }else {
-                        if task.tick() <= 0 {
-                            break // 异常的任务
+                        Plan::Paused => {
+                            if !control.wait_resume().await {
+                                break;
+                            }
+                            continue;
                         }
+                        Plan::Wait(v) => { v }
+                    };
 
-                        if debug {
-                            debug!("make next ticker sec:{} id:{}",(task.tick()) as u64,task.id());
+                    // 等待一下，让出这个线程，被停止时立即唤醒
+                    match control.sleep_since(wait,seen).await {
+                        Wake::Cancelled => { break; }
+                        Wake::Interrupted => {
+                            // 状态变化，重新计算
+                            pacer.interrupted();
+                            continue;
                         }
-
-                        time::sleep(time::Duration::from_millis( task.tick() )).await;
+                        Wake::Elapsed => {}
                     }
+                    let scheduled_at = match pacer.elapsed() {
+                        Fire::Again => { continue; }
+                        Fire::Drain => {
+                            drain = true;
+                            break; // 结束这个任务
+                        }
+                        Fire::Run(v) => { v }
+                    };
 
-                    if max_count  > 0 && r_count >= max_count {
-                        if debug {
-                            debug!("task finished:{}",task.id());
+                    let admitted = match &admitted {
+                        None => {
+                            let r_count = control.add_run();//计数
+                            if debug {
+                                debug!("task run count:{} id:{}",r_count,task_id);
+                            }
+                            let slot = RunSlot { handle:task_handle.clone(), index:r_count, scheduled_at };
+                            if !run_once(&task,slot,policy).await {
+                                break;
+                            }
+                            continue;
                         }
-                        break; // 结束这个任务
-                    }
+                        Some(v) => { v.clone() }
+                    };
 
-                    r_count+=1;//计数
+                    // 仍有执行未结束且没有空位时跳过这一次
+                    let permit = match admitted.try_acquire_owned() {
+                        Ok(v) => { v }
+                        Err(_) => {
+                            if debug {
+                                debug!("task overlap skipped id:{}",task_id);
+                            }
+                            continue;
+                        }
+                    };
+                    let r_count = control.add_run();//计数
                     if debug {
-                        debug!("task run count:{} id:{}",r_count,task.id());
+                        debug!("task run count:{} id:{}",r_count,task_id);
                     }
-                    task.execute(task.id());
-
-                    // 检测一下，是不是被强制结束了
-                    let sr = rx_spwan.try_recv();
-                    match sr {
-                        Err(e) => {  },
-                        Ok(val) => {
-                            if task.id() == val {
-                                // 结束这个任务
-                                debug!("task stopped:{}",task.id());
-                                return;
-                            }
-                        },
+                    let slot = RunSlot { handle:task_handle.clone(), index:r_count, scheduled_at };
+                    let (prev,next) = if ordered {
+                        let (tx,rx) = oneshot::channel();
+                        (turn.replace(rx),Some(tx))
+                    }else {
+                        (None,None)
+                    };
+                    let (task,control,outstanding) = (task.clone(),control.clone(),outstanding.clone());
+                    if let Some(v) = &outstanding {
+                        v.add();
+                    }
+                    let busy = control.timer().busy();
+                    tokio::spawn(async move {
+                        let _busy = busy;
+                        let _permit = permit;
+                        if let Some(prev) = prev {
+                            // 先恢复自己的活动再释放上一次执行交来的活动
+                            let _turn = {
+                                let _idle = control.timer().idle();
+                                prev.await
+                            };
+                        }
+                        if !control.status().is_terminal() {
+                            run_once(&task,slot,policy).await;
+                        }
+                        // 先交出轮次和活动，再结束自己的活动
+                        if let Some(next) = next {
+                            let _ = next.send(control.timer().busy());
+                        }
+                        if let Some(v) = &outstanding {
+                            v.done(control.timer());
+                        }
+                    });
+                }
+
+                if drain {
+                    // 等待还在执行的任务结束
+                    if let Some(outstanding) = &outstanding {
+                        tokio::select! {
+                            _ = outstanding.wait(control.timer()) => {}
+                            _ = control.wait() => {}
+                        }
                     }
                 }
+                end_task(&control,&registry,drain,debug);
             });
+            Ok(handle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::thread::threads::TaskPool;
+    use crate::schedule::schedule::ClosuresAction;
+    use crate::errors::errors::TErrorKind;
+    use crate::task::task::TaskStatus;
+    use crate::schedule::schedule::{Schedule, TaskOptions, AsyncClosuresAction, FailurePolicy, RetryPolicy, Backoff, OverlapPolicy, TickMode, MissedTickBehavior, Jitter, RunLimit, ExecMode};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32,Ordering};
+    use crate::scheduler::scheduler::{Config, TimerBackend};
+    use crate::clock::clock::ManualClock;
+    use crate::testing::testing::TestScheduler;
+    use chrono::{DateTime, Local, TimeZone};
+    use crate::task::task::TaskHandle;
+    use std::time;
+
+    fn new_pool(count:i32) -> TaskPool {
+        TaskPool::new(&Config{ thread_count:count, ..Config::default() })
+    }
+
+    #[test]
+    fn test_stop_targets_one_task() {
+        let pool = new_pool(2);
+        let first = Arc::new(AtomicU32::new(0));
+        let second = Arc::new(AtomicU32::new(0));
+
+        let c1 = first.clone();
+        pool.spawn(Arc::new(ClosuresAction::new("",1,0,time::Duration::from_millis(20),move |_| {
+            c1.fetch_add(1,Ordering::SeqCst);
+        }))).unwrap();
+        let c2 = second.clone();
+        pool.spawn(Arc::new(ClosuresAction::new("",2,0,time::Duration::from_millis(20),move |_| {
+            c2.fetch_add(1,Ordering::SeqCst);
+        }))).unwrap();
+
+        pool.stop_task(1).unwrap();
+        std::thread::sleep(time::Duration::from_millis(150));
+
+        assert_eq!(first.load(Ordering::SeqCst),0);
+        assert!(second.load(Ordering::SeqCst) > 0);
+        assert!(matches!(pool.stop_task(1).unwrap_err().kind(),TErrorKind::TaskNotFound));
+    }
+
+    #[test]
+    fn test_stop_and_reuse_id() {
+        let pool = new_pool(1);
+        pool.spawn(Arc::new(ClosuresAction::new("",7,0,time::Duration::from_secs(3600),|_| {}))).unwrap();
+        pool.stop_task(7).unwrap();
+
+        // 停止后同样的ID可以重新注册
+        assert!(pool.spawn(Arc::new(ClosuresAction::new("",7,0,time::Duration::from_secs(3600),|_| {}))).is_ok());
+        assert!(matches!(pool.spawn(Arc::new(ClosuresAction::new("",7,0,time::Duration::from_secs(3600),|_| {}))).unwrap_err().kind(),TErrorKind::TaskExists));
+    }
+
+    #[test]
+    fn test_handle_control() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+        let count = Arc::new(AtomicU32::new(0));
+
+        let c1 = count.clone();
+        let handle = test.spawn_trait(Arc::new(ClosuresAction::new("",3,0,time::Duration::from_millis(20),move |_| {
+            c1.fetch_add(1,Ordering::SeqCst);
+        }))).unwrap();
+        assert_eq!(handle.id(),3);
+        assert_eq!(handle.status(),TaskStatus::Scheduled);
+
+        test.advance(time::Duration::from_millis(70));
+        assert_eq!(count.load(Ordering::SeqCst),3);
+        handle.pause().unwrap();
+        test.settle();
+        assert_eq!(handle.status(),TaskStatus::Paused);
+        test.advance(time::Duration::from_millis(80));
+        assert_eq!(count.load(Ordering::SeqCst),3);
+        assert_eq!(handle.run_count(),3);
+
+        // 暂停时剩余10ms，恢复后先补上这一次
+        handle.resume().unwrap();
+        test.settle();
+        test.advance(time::Duration::from_millis(80));
+        let runs = test.runs_of(3).iter().map(|v| (v.started_at - start).num_milliseconds()).collect::<Vec<_>>();
+        assert_eq!(runs,vec![20,40,60,160,180,200,220]);
+
+        handle.cancel();
+        let done = handle.clone();
+        assert_eq!(test.block_on_rt(async move { done.await }).unwrap(),TaskStatus::Cancelled);
+        assert!(handle.next_run().is_none());
+        assert!(matches!(test.stop_ticker(3).unwrap_err().kind(),TErrorKind::TaskNotFound));
+    }
+
+    #[test]
+    fn test_handle_await_finished() {
+        let pool = new_pool(1);
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("",4,2,time::Duration::from_millis(10),|_| {}))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(async { handle.wait().await }),TaskStatus::Finished);
+        assert_eq!(handle.run_count(),2);
+    }
+
+    #[test]
+    fn test_pause_keeps_remaining() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+        test.spawn_trait(Arc::new(ClosuresAction::new("",5,1,time::Duration::from_millis(400),|_| {}))).unwrap();
+
+        test.advance(time::Duration::from_millis(200));
+        test.pause_ticker(5).unwrap();
+        test.settle();
+        test.advance(time::Duration::from_millis(400));
+        assert!(test.runs_of(5).is_empty());
+
+        // 剩余200ms，而不是重新等待400ms
+        test.resume_ticker(5).unwrap();
+        test.settle();
+        test.advance(time::Duration::from_millis(199));
+        assert!(test.runs_of(5).is_empty());
+        test.advance(time::Duration::from_millis(1));
+        let runs = test.runs_of(5).iter().map(|v| v.started_at).collect::<Vec<_>>();
+        assert_eq!(runs,vec![start + chrono::Duration::milliseconds(800)]);
+        assert!(matches!(test.pause_ticker(99).unwrap_err().kind(),TErrorKind::TaskNotFound));
+    }
+
+    #[test]
+    fn test_fixed_rate_pause() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+        let opts = TaskOptions{ tick_mode:TickMode::FixedRate(MissedTickBehavior::Burst), ..TaskOptions::default() };
+        let handle = test.spawn_with(Schedule::Tick(time::Duration::from_millis(100)),0,opts,|_| {}).unwrap();
+
+        test.advance(time::Duration::from_millis(250));
+        handle.pause().unwrap();
+        test.settle();
+        test.advance(time::Duration::from_secs(10));
+        assert_eq!(test.runs_of(handle.id()).len(),2);
+
+        // 恢复后按剩余的50ms重新锚定，暂停期间的间隔不会补执行
+        handle.resume().unwrap();
+        test.settle();
+        test.advance(time::Duration::from_millis(250));
+        let starts = test.runs_of(handle.id()).iter().map(|v| (v.started_at - start).num_milliseconds()).collect::<Vec<_>>();
+        assert_eq!(starts,vec![100,200,10300,10400,10500]);
+        handle.cancel();
+    }
+
+    #[test]
+    fn test_reschedule_keeps_id_and_count() {
+        let pool = new_pool(1);
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("",6,0,time::Duration::from_millis(20),|_| {}))).unwrap();
+
+        std::thread::sleep(time::Duration::from_millis(90));
+        let runs = handle.run_count();
+        assert!(runs > 0);
+
+        pool.reschedule_task(6,Schedule::from("Week 1 05:00:00")).unwrap();
+        std::thread::sleep(time::Duration::from_millis(60));
+        assert_eq!(handle.id(),6);
+        assert_eq!(handle.run_count(),runs);
+        assert!(handle.next_run().unwrap() > chrono::Local::now() + chrono::Duration::seconds(1));
+
+        pool.reschedule_task(6,Schedule::Tick(time::Duration::from_millis(20))).unwrap();
+        std::thread::sleep(time::Duration::from_millis(90));
+        assert!(handle.run_count() > runs);
+
+        assert!(pool.reschedule_task(6,Schedule::from("Day 25:00")).is_err());
+        assert!(pool.reschedule_task(6,Schedule::Tick(time::Duration::from_millis(0))).is_err());
+        handle.cancel();
+
+        // 已结束的任务不能再修改
+        let err = handle.reschedule(Schedule::Tick(time::Duration::from_millis(20))).unwrap_err();
+        assert!(matches!(err.kind(),TErrorKind::TaskNotFound));
+        assert!(handle.pause().is_err() && handle.resume().is_err());
+    }
+
+    #[test]
+    fn test_list_and_inspect() {
+        let pool = new_pool(1);
+        pool.spawn(Arc::new(ClosuresAction::new("Day 05:00:00",12,3,time::Duration::from_millis(0),|_| {}))).unwrap();
+        pool.spawn(Arc::new(ClosuresAction::new("",11,0,time::Duration::from_millis(20),|_| {}))).unwrap();
+        std::thread::sleep(time::Duration::from_millis(50));
+
+        let infos = pool.list_tasks().unwrap();
+        assert_eq!(infos.iter().map(|v| v.id).collect::<Vec<u64>>(),vec![11,12]);
+
+        let date = pool.task_info(12).unwrap();
+        assert_eq!(date.schedule,Schedule::from("Day 05:00:00"));
+        assert_eq!(date.run_limit,RunLimit::Times(3));
+        assert_eq!(date.runs_left,Some(3));
+        assert_eq!(date.run_count,0);
+        assert!(date.last_run.is_none());
+        assert!(date.next_run.is_some());
+        assert_eq!(date.status,TaskStatus::Scheduled);
+
+        let ticker = pool.task_info(11).unwrap();
+        assert_eq!(ticker.schedule,Schedule::Tick(time::Duration::from_millis(20)));
+        assert!(ticker.run_count > 0);
+        assert!(ticker.last_run.is_some());
+
+        pool.stop_task(11).unwrap();
+        assert!(pool.task_info(11).is_err());
+        assert_eq!(pool.list_tasks().unwrap().len(),1);
+    }
+
+    #[test]
+    fn test_group_control() {
+        let pool = new_pool(1);
+        let opts = TaskOptions{ tags:vec![String::from("player")], ..TaskOptions::default() };
+        for id in 20..23 {
+            pool.spawn(Arc::new(ClosuresAction::with_options(Schedule::Tick(time::Duration::from_secs(60)),id,0,opts.clone(),|_| {}))).unwrap();
+        }
+        pool.spawn(Arc::new(ClosuresAction::new("",23,0,time::Duration::from_secs(60),|_| {}))).unwrap();
+
+        assert_eq!(pool.list_group("player").unwrap().iter().map(|v| v.id).collect::<Vec<u64>>(),vec![20,21,22]);
+        assert_eq!(pool.pause_group("player").unwrap(),3);
+        assert!(pool.list_group("player").unwrap().iter().all(|v| v.status == TaskStatus::Paused));
+        assert_eq!(pool.task_info(23).unwrap().status,TaskStatus::Scheduled);
+
+        assert_eq!(pool.stop_group("player").unwrap(),3);
+        assert!(pool.list_group("player").unwrap().is_empty());
+        assert_eq!(pool.list_tasks().unwrap().len(),1);
+        assert_eq!(pool.stop_group("player").unwrap(),0);
+    }
+
+    #[test]
+    fn test_async_action() {
+        let pool = new_pool(1);
+        let count = Arc::new(AtomicU32::new(0));
+
+        let c1 = count.clone();
+        let handle = pool.spawn_async(Arc::new(AsyncClosuresAction::new(Schedule::Tick(time::Duration::from_millis(10)),30,3,TaskOptions::default(),move |_| {
+            let c2 = c1.clone();
+            async move {
+                tokio::time::sleep(time::Duration::from_millis(5)).await;
+                c2.fetch_add(1,Ordering::SeqCst);
+            }
+        }))).unwrap();
+
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+        assert_eq!(count.load(Ordering::SeqCst),3);
+    }
+
+    #[test]
+    fn test_panic_isolation() {
+        let pool = new_pool(1);
+        let count = Arc::new(AtomicU32::new(0));
+
+        // 默认继续执行
+        let c1 = count.clone();
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("",40,3,time::Duration::from_millis(10),move |_| {
+            if c1.fetch_add(1,Ordering::SeqCst) == 0 {
+                panic!("first run broken");
+            }
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+        assert_eq!(count.load(Ordering::SeqCst),3);
+        assert_eq!(handle.info().last_error,Some(String::from("panic: first run broken")));
+        assert_eq!(handle.info().failures,0);
+
+        // 连续失败两次后停止
+        let opts = TaskOptions{ failure_policy:FailurePolicy::StopAfter(2), ..TaskOptions::default() };
+        let handle = pool.spawn_async(Arc::new(AsyncClosuresAction::new(Schedule::Tick(time::Duration::from_millis(10)),41,0,opts,|id| async move {
+            panic!("broken task {}",id);
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Failed);
+        assert_eq!(handle.run_count(),2);
+        assert_eq!(handle.info().failures,2);
+        assert_eq!(handle.info().last_error,Some(String::from("panic: broken task 41")));
+    }
+
+    #[test]
+    fn test_retry_before_next_slot() {
+        let pool = new_pool(1);
+        let count = Arc::new(AtomicU32::new(0));
+
+        // 前两次失败，重试后成功
+        let c1 = count.clone();
+        let opts = TaskOptions{
+            retry:Some(RetryPolicy{ max_attempts:3, backoff:Backoff::Fixed(time::Duration::from_millis(5)), jitter:time::Duration::from_millis(0) }),
+            ..TaskOptions::default()
+        };
+        let handle = pool.spawn(Arc::new(ClosuresAction::fallible(Schedule::Tick(time::Duration::from_millis(50)),50,1,opts,move |_| {
+            let n = c1.fetch_add(1,Ordering::SeqCst);
+            if n < 2 { Err(format!("db down {}",n)) } else { Ok(()) }
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+        assert_eq!(count.load(Ordering::SeqCst),3);
+        assert_eq!(handle.run_count(),1);
+        assert_eq!(handle.info().failures,0);
+        assert_eq!(handle.info().last_error,Some(String::from("db down 1")));
+
+        // 重试的等待超过下一次执行，不再重试
+        let count = Arc::new(AtomicU32::new(0));
+        let c2 = count.clone();
+        let opts = TaskOptions{
+            retry:Some(RetryPolicy{ max_attempts:3, backoff:Backoff::Fixed(time::Duration::from_millis(100)), jitter:time::Duration::from_millis(0) }),
+            ..TaskOptions::default()
+        };
+        let handle = pool.spawn_async(Arc::new(AsyncClosuresAction::fallible(Schedule::Tick(time::Duration::from_millis(20)),51,2,opts,move |_| {
+            c2.fetch_add(1,Ordering::SeqCst);
+            async move { Err::<(),&str>("always") }
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+        assert_eq!(count.load(Ordering::SeqCst),2);
+        assert_eq!(handle.info().failures,2);
+    }
+
+    #[test]
+    fn test_cancel_during_retry_backoff() {
+        let clock = ManualClock::new(Local.ymd(2026,3,2).and_hms(5,0,0));
+        let pool = TaskPool::new(&Config{ thread_count:2, clock:Arc::new(clock.clone()), ..Config::default() });
+        let count = Arc::new(AtomicU32::new(0));
+
+        // 并发执行时主循环等待下一次，执行体等待重试，取消要同时唤醒两者
+        let c1 = count.clone();
+        let opts = TaskOptions{
+            retry:Some(RetryPolicy{ max_attempts:3, backoff:Backoff::Fixed(time::Duration::from_secs(5)), jitter:time::Duration::from_millis(0) }),
+            overlap:OverlapPolicy::Concurrent(2),
+            ..TaskOptions::default()
+        };
+        let handle = pool.spawn(Arc::new(ClosuresAction::fallible(Schedule::Tick(time::Duration::from_secs(10)),52,0,opts,move |_| {
+            c1.fetch_add(1,Ordering::SeqCst);
+            Err::<(),&str>("always")
+        }))).unwrap();
+        wait_until(|| clock.sleepers() == 1);
+        clock.advance(time::Duration::from_secs(10));
+        wait_until(|| count.load(Ordering::SeqCst) == 1 && clock.sleepers() == 2);
+
+        handle.cancel();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Cancelled);
+        wait_until(|| clock.sleepers() == 0);
+        assert_eq!(count.load(Ordering::SeqCst),1);
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let fixed = RetryPolicy{ max_attempts:3, backoff:Backoff::Fixed(time::Duration::from_secs(5)), jitter:time::Duration::from_secs(0) };
+        assert_eq!(fixed.delay(3),time::Duration::from_secs(5));
+
+        let linear = RetryPolicy{ backoff:Backoff::Linear(time::Duration::from_secs(5)), ..fixed };
+        assert_eq!(linear.delay(3),time::Duration::from_secs(15));
+
+        let exp = RetryPolicy{ backoff:Backoff::Exponential{ base:time::Duration::from_secs(5), max:time::Duration::from_secs(30) }, ..fixed };
+        assert_eq!(exp.delay(1),time::Duration::from_secs(5));
+        assert_eq!(exp.delay(3),time::Duration::from_secs(20));
+        assert_eq!(exp.delay(40),time::Duration::from_secs(30));
+
+        let jitter = RetryPolicy{ jitter:time::Duration::from_secs(2), ..fixed };
+        let d = jitter.delay_with_jitter(1);
+        assert!(d >= time::Duration::from_secs(5) && d <= time::Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_timeout_moves_on() {
+        let pool = TaskPool::new(&Config{ thread_count:1, default_timeout:Some(time::Duration::from_millis(30)), ..Config::default() });
+
+        // 卡住的任务体在默认超时后被中止，之后继续按计划执行
+        let hung = pool.spawn_async(Arc::new(AsyncClosuresAction::new(Schedule::Tick(time::Duration::from_millis(10)),60,2,TaskOptions::default(),|_| {
+            std::future::pending::<()>()
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(hung.wait()),TaskStatus::Finished);
+        assert_eq!(hung.run_count(),2);
+        assert_eq!(hung.info().failures,2);
+        assert_eq!(hung.info().last_error,Some(String::from("timeout: exceeded 30ms")));
+
+        // 任务自己的超时优先
+        let count = Arc::new(AtomicU32::new(0));
+        let c1 = count.clone();
+        let opts = TaskOptions{ timeout:Some(time::Duration::from_millis(200)), ..TaskOptions::default() };
+        let slow = pool.spawn_async(Arc::new(AsyncClosuresAction::new(Schedule::Tick(time::Duration::from_millis(10)),61,1,opts,move |_| {
+            let c1 = c1.clone();
+            async move {
+                tokio::time::sleep(time::Duration::from_millis(60)).await;
+                c1.fetch_add(1,Ordering::SeqCst);
+            }
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(slow.wait()),TaskStatus::Finished);
+        assert_eq!(count.load(Ordering::SeqCst),1);
+        assert_eq!(slow.info().failures,0);
+    }
+
+    #[test]
+    fn test_sync_timeout_moves_on() {
+        let pool = new_pool(1);
+
+        // 第一次执行卡住且不检查取消标记，超时后调度继续；独占线程被占住，排在后面的执行也超时
+        for (id,mode,done,failures) in [(62,ExecMode::Inline,2,0),(63,ExecMode::Blocking,2,0),(64,ExecMode::DedicatedThread,0,3)] {
+            let gate = Arc::new((std::sync::Mutex::new(false),std::sync::Condvar::new()));
+            let calls = Arc::new((AtomicU32::new(0),AtomicU32::new(0)));
+            let (g,c) = (gate.clone(),calls.clone());
+            let opts = TaskOptions{ timeout:Some(time::Duration::from_millis(10)), exec_mode:mode, ..TaskOptions::default() };
+            let handle = pool.spawn(Arc::new(ClosuresAction::with_options(Schedule::Tick(time::Duration::from_millis(5)),id,3,opts,move |_| {
+                if c.0.fetch_add(1,Ordering::SeqCst) == 0 {
+                    let mut released = g.0.lock().unwrap();
+                    while !*released {
+                        released = g.1.wait(released).unwrap();
+                    }
+                }
+                c.1.fetch_add(1,Ordering::SeqCst);
+            }))).unwrap();
+            assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished,"{:?}",mode);
+            assert_eq!(handle.run_count(),3,"{:?}",mode);
+            assert_eq!(calls.1.load(Ordering::SeqCst),done,"{:?}",mode);
+            assert_eq!(handle.info().failures,failures,"{:?}",mode);
+            assert_eq!(handle.info().last_error,Some(String::from("timeout: exceeded 10ms")),"{:?}",mode);
+
+            // 放开卡住的任务体
+            *gate.0.lock().unwrap() = true;
+            gate.1.notify_all();
+        }
+    }
+
+    // 每次执行在测试时钟上花费150ms，记录同时执行的最大数量
+    fn overlap_task(test:&TestScheduler,limit:i32,overlap:OverlapPolicy,gauge:Arc<(AtomicU32,AtomicU32)>) -> TaskHandle {
+        let opts = TaskOptions{ overlap, ..TaskOptions::default() };
+        test.spawn_async_ctx(Schedule::Tick(time::Duration::from_millis(20)),limit,opts,move |ctx| {
+            let gauge = gauge.clone();
+            async move {
+                let now = gauge.0.fetch_add(1,Ordering::SeqCst) + 1;
+                gauge.1.fetch_max(now,Ordering::SeqCst);
+                ctx.sleep(time::Duration::from_millis(150)).await;
+                gauge.0.fetch_sub(1,Ordering::SeqCst);
+                Ok::<(),String>(())
+            }
+        }).unwrap()
+    }
+
+    // 每次执行的计划时间和开始时间，相对开始的毫秒数
+    fn overlap_runs(test:&TestScheduler,start:DateTime<Local>,id:u64) -> Vec<(i64,i64)> {
+        test.runs_of(id).iter().map(|v| ((v.scheduled_at - start).num_milliseconds(),(v.started_at - start).num_milliseconds())).collect()
+    }
+
+    #[test]
+    fn test_overlap_policy() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+
+        // 并行执行，按自己的间隔触发，结束前等待所有执行完成
+        let test = TestScheduler::new(start);
+        let gauge = Arc::new((AtomicU32::new(0),AtomicU32::new(0)));
+        let handle = overlap_task(&test,4,OverlapPolicy::Concurrent(4),gauge.clone());
+        test.advance(time::Duration::from_millis(229));
+        assert_eq!(gauge.1.load(Ordering::SeqCst),4);
+        assert_eq!(gauge.0.load(Ordering::SeqCst),1);
+        assert_eq!(handle.status(),TaskStatus::Running);
+        test.advance(time::Duration::from_millis(1));
+        assert_eq!(overlap_runs(&test,start,handle.id()),vec![(20,20),(40,40),(60,60),(80,80)]);
+        assert_eq!(gauge.0.load(Ordering::SeqCst),0);
+        assert_eq!(handle.status(),TaskStatus::Finished);
+
+        // 排队依次执行，每次在上一次结束时开始
+        let test = TestScheduler::new(start);
+        let gauge = Arc::new((AtomicU32::new(0),AtomicU32::new(0)));
+        let handle = overlap_task(&test,3,OverlapPolicy::Queue(2),gauge.clone());
+        test.advance(time::Duration::from_secs(1));
+        assert_eq!(overlap_runs(&test,start,handle.id()),vec![(20,20),(40,170),(60,320)]);
+        assert_eq!(gauge.1.load(Ordering::SeqCst),1);
+        assert_eq!(handle.run_count(),3);
+        assert_eq!(handle.status(),TaskStatus::Finished);
+
+        // 仍在执行时跳过，执行结束后的下一次间隔才触发
+        let test = TestScheduler::new(start);
+        let gauge = Arc::new((AtomicU32::new(0),AtomicU32::new(0)));
+        let handle = overlap_task(&test,0,OverlapPolicy::SkipIfRunning,gauge.clone());
+        test.advance(time::Duration::from_millis(495));
+        assert_eq!(overlap_runs(&test,start,handle.id()),vec![(20,20),(180,180),(340,340)]);
+        assert_eq!(gauge.1.load(Ordering::SeqCst),1);
+        assert_eq!(handle.run_count(),3);
+        handle.cancel();
+    }
+
+    #[test]
+    fn test_fixed_rate() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+
+        // 每次执行在测试时钟上花费30ms，固定频率下仍然按50ms的节奏执行
+        let clock = test.clock().clone();
+        let opts = TaskOptions{ tick_mode:TickMode::FixedRate(MissedTickBehavior::Burst), ..TaskOptions::default() };
+        let handle = test.spawn_with(Schedule::Tick(time::Duration::from_millis(50)),4,opts,move |_| {
+            clock.advance(time::Duration::from_millis(30));
+        }).unwrap();
+
+        test.advance(time::Duration::from_secs(1));
+        let starts = test.runs_of(handle.id()).iter().map(|v| (v.started_at - start).num_milliseconds()).collect::<Vec<_>>();
+        assert_eq!(starts,vec![50,100,150,200]);
+        assert_eq!(handle.status(),TaskStatus::Finished);
+    }
+
+    #[test]
+    fn test_missed_tick_behavior() {
+        let last = tokio::time::Instant::now();
+        let period = time::Duration::from_millis(100);
+
+        // 没有落后时都按原来的节奏
+        let now = last + time::Duration::from_millis(40);
+        assert_eq!(MissedTickBehavior::Skip.next_due(last,period,now),last + period);
+
+        let now = last + time::Duration::from_millis(350);
+        assert_eq!(MissedTickBehavior::Burst.next_due(last,period,now),last + period);
+        assert_eq!(MissedTickBehavior::Delay.next_due(last,period,now),now + period);
+        assert_eq!(MissedTickBehavior::Skip.next_due(last,period,now),last + time::Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_jitter() {
+        let base = time::Duration::from_secs(10);
+        assert_eq!(Jitter::None.delay(base),time::Duration::from_secs(0));
+        for _ in 0..20 {
+            assert!(Jitter::Range(time::Duration::from_millis(500)).delay(base) <= time::Duration::from_millis(500));
+            assert!(Jitter::Percent(10).delay(base) <= time::Duration::from_secs(1));
+        }
+
+        // 相同的key每次都一样，不同的key分散在窗口内
+        let window = time::Duration::from_secs(600);
+        let spread = |key:&str| Jitter::Spread{ key:String::from(key), window }.delay(base);
+        assert_eq!(spread("player-1"),spread("player-1"));
+        let mut offsets = (0..100).map(|i| spread(&format!("player-{}",i))).collect::<Vec<time::Duration>>();
+        assert!(offsets.iter().all(|v| *v < window));
+        offsets.sort();
+        offsets.dedup();
+        assert!(offsets.len() > 90);
+
+        // 日期任务的下一次执行时间加上固定的偏移
+        let pool = new_pool(1);
+        let opts = TaskOptions{ jitter:Jitter::Spread{ key:String::from("player-7"), window }, ..TaskOptions::default() };
+        let handle = pool.spawn(Arc::new(ClosuresAction::with_options(Schedule::from("Every 1h Epoch"),90,1,opts,|_| {}))).unwrap();
+        std::thread::sleep(time::Duration::from_millis(50));
+        let expect = crate::parsers::parsers::parser_next("Every 1h Epoch").unwrap() + chrono::Duration::from_std(spread("player-7")).unwrap();
+        let diff = (handle.next_run().unwrap() - expect).num_milliseconds().abs();
+        assert!(diff < 1000);
+        handle.cancel();
+    }
+
+    #[test]
+    fn test_spread_keeps_tick() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let jitter = Jitter::Spread{ key:String::from("player-7"), window:time::Duration::from_secs(1) };
+        let spread = jitter.spread().as_millis() as i64;
+        assert!(spread > 0);
+
+        // 固定分散只移动第一次执行，之后的间隔仍然是100ms
+        for tick_mode in [TickMode::FixedDelay,TickMode::FixedRate(MissedTickBehavior::Burst)] {
+            let test = TestScheduler::new(start);
+            let opts = TaskOptions{ jitter:jitter.clone(), tick_mode, ..TaskOptions::default() };
+            let handle = test.spawn_with(Schedule::Tick(time::Duration::from_millis(100)),3,opts,|_| {}).unwrap();
+            test.advance(time::Duration::from_secs(2));
+            let starts = test.runs_of(handle.id()).iter().map(|v| (v.started_at - start).num_milliseconds()).collect::<Vec<_>>();
+            assert_eq!(starts,vec![100 + spread,200 + spread,300 + spread],"{:?}",tick_mode);
+        }
+    }
+
+    #[test]
+    fn test_start_and_end_bounds() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+
+        // 初始延迟后执行第一次
+        let opts = TaskOptions{ initial_delay:Some(time::Duration::from_millis(100)), ..TaskOptions::default() };
+        let delayed = test.spawn_with(Schedule::Tick(time::Duration::from_millis(20)),1,opts,|_| {}).unwrap();
+
+        // 到结束时间后自动结束
+        let opts = TaskOptions{ end_at:Some(start + chrono::Duration::milliseconds(110)), ..TaskOptions::default() };
+        let ended = test.spawn_with(Schedule::Tick(time::Duration::from_millis(20)),0,opts,|_| {}).unwrap();
+
+        // 日期任务从开始时间后的下一个日期执行
+        let opts = TaskOptions{ start_at:Some(start + chrono::Duration::milliseconds(130)), ..TaskOptions::default() };
+        let dated = test.spawn_with(Schedule::from("Every 50ms Epoch"),1,opts,|_| {}).unwrap();
+
+        test.advance(time::Duration::from_secs(1));
+        let runs = |h:&TaskHandle| test.runs_of(h.id()).iter().map(|v| (v.started_at - start).num_milliseconds()).collect::<Vec<_>>();
+        assert_eq!(runs(&delayed),vec![100]);
+        assert_eq!(runs(&ended),vec![20,40,60,80,100]);
+        assert_eq!(runs(&dated),vec![150]);
+        for h in [delayed,ended,dated] {
+            assert_eq!(h.status(),TaskStatus::Finished);
+        }
+    }
+
+    #[test]
+    fn test_run_limit() {
+        let start = Local.ymd(2026,3,2).and_hms(0,0,0);
+        let test = TestScheduler::new(start);
+
+        // 最后一次执行后立即结束，不再等待一个间隔
+        let handle = test.spawn_ticker(time::Duration::from_millis(100),RunLimit::Times(2),|_| {}).unwrap();
+        assert_eq!(handle.runs_left(),Some(2));
+        test.advance(time::Duration::from_millis(200));
+        assert_eq!(handle.status(),TaskStatus::Finished);
+        assert_eq!(handle.runs_left(),Some(0));
+        assert!(test.task_info(handle.id()).is_err());
+
+        // 先到截止时间
+        let until = test.now() + chrono::Duration::milliseconds(70);
+        let handle = test.spawn_ticker(time::Duration::from_millis(20),RunLimit::TimesOrUntil(10,until),|_| {}).unwrap();
+        test.advance(time::Duration::from_secs(1));
+        assert_eq!(handle.status(),TaskStatus::Finished);
+        assert_eq!(handle.run_count(),3);
+        assert_eq!(handle.runs_left(),Some(7));
+
+        let handle = test.spawn_ticker(time::Duration::from_millis(20),0,|_| {}).unwrap();
+        assert_eq!(handle.info().run_limit,RunLimit::Forever);
+        assert_eq!(handle.runs_left(),None);
+        handle.cancel();
+    }
+
+    #[test]
+    fn test_exec_context() {
+        let pool = new_pool(1);
+
+        // 执行序号、剩余次数，在任务体内停止自己
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let action = ClosuresAction::with_context(Schedule::Tick(time::Duration::from_millis(10)),120,5,TaskOptions::default(),move |ctx| -> Result<(),String> {
+            assert_eq!(ctx.id,120);
+            assert!(ctx.started_at >= ctx.scheduled_at);
+            record.lock().unwrap().push((ctx.run_index,ctx.runs_left));
+            if ctx.run_index == 3 {
+                ctx.stop();
+            }
+            Ok(())
+        });
+        let handle = pool.spawn(Arc::new(action)).unwrap();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Cancelled);
+        assert_eq!(*seen.lock().unwrap(),vec![(1,Some(4)),(2,Some(3)),(3,Some(2))]);
+
+        // 超时后同步任务体通过取消标记退出，调度不等它返回
+        let (tx,rx) = std::sync::mpsc::channel();
+        let tx = std::sync::Mutex::new(tx);
+        let opts = TaskOptions{ timeout:Some(time::Duration::from_millis(20)), exec_mode:ExecMode::Blocking, ..TaskOptions::default() };
+        let action = ClosuresAction::with_context(Schedule::Tick(time::Duration::from_millis(10)),121,1,opts,move |ctx| -> Result<(),String> {
+            while !ctx.is_cancelled() {
+                std::thread::sleep(time::Duration::from_millis(5));
+            }
+            let _ = tx.lock().unwrap().send(ctx.run_index);
+            Ok(())
+        });
+        let handle = pool.spawn(Arc::new(action)).unwrap();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+        assert!(handle.info().last_error.unwrap().starts_with("timeout"));
+        assert_eq!(rx.recv_timeout(time::Duration::from_secs(5)),Ok(1));
+
+        // 异步任务体可以等待取消
+        let exited = Arc::new(AtomicU32::new(0));
+        let flag = exited.clone();
+        let action = AsyncClosuresAction::with_context(Schedule::Tick(time::Duration::from_millis(10)),122,0,TaskOptions::default(),move |ctx| {
+            let flag = flag.clone();
+            async move {
+                ctx.token().cancelled().await;
+                flag.fetch_add(1,Ordering::SeqCst);
+                Ok::<(),String>(())
+            }
+        });
+        let handle = pool.spawn_async(Arc::new(action)).unwrap();
+        std::thread::sleep(time::Duration::from_millis(30));
+        assert_eq!(exited.load(Ordering::SeqCst),0);
+        handle.cancel();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Cancelled);
+        std::thread::sleep(time::Duration::from_millis(20));
+        assert_eq!(exited.load(Ordering::SeqCst),1);
+    }
+
+    #[test]
+    fn test_exec_mode() {
+        let pool = new_pool(1);
+
+        // 阻塞的任务体不占用唯一的工作线程，其他任务按时执行
+        let ticks = Arc::new(AtomicU32::new(0));
+        let t1 = ticks.clone();
+        let ticker = pool.spawn(Arc::new(ClosuresAction::new("",130,0,time::Duration::from_millis(10),move |_| {
+            t1.fetch_add(1,Ordering::SeqCst);
+        }))).unwrap();
+        // 阻塞的任务体一直占着线程，直到另一个任务执行了5次
+        let t2 = ticks.clone();
+        let opts = TaskOptions{ exec_mode:ExecMode::Blocking, ..TaskOptions::default() };
+        let heavy = pool.spawn(Arc::new(ClosuresAction::with_options(Schedule::Tick(time::Duration::from_millis(10)),131,1,opts,move |_| {
+            let begin = time::Instant::now();
+            while t2.load(Ordering::SeqCst) < 5 && begin.elapsed() < time::Duration::from_secs(5) {
+                std::thread::sleep(time::Duration::from_millis(1));
+            }
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(heavy.wait()),TaskStatus::Finished);
+        assert!(ticks.load(Ordering::SeqCst) >= 5);
+        ticker.cancel();
+
+        // 独占线程中依次执行，panic后线程继续使用
+        let names = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = names.clone();
+        let opts = TaskOptions{ exec_mode:ExecMode::DedicatedThread, ..TaskOptions::default() };
+        let dedicated = pool.spawn(Arc::new(ClosuresAction::with_options(Schedule::Tick(time::Duration::from_millis(10)),132,3,opts,move |id| {
+            let mut names = record.lock().unwrap();
+            names.push(std::thread::current().name().map(String::from));
+            if names.len() == 1 {
+                drop(names);
+                panic!("dedicated:{}",id);
+            }
+        }))).unwrap();
+        assert_eq!(pool.rt_handle().block_on(dedicated.wait()),TaskStatus::Finished);
+        assert_eq!(*names.lock().unwrap(),vec![Some(String::from("grape-timer-132"));3]);
+        assert_eq!(dedicated.info().failures,0);
+        assert_eq!(dedicated.info().last_error,Some(String::from("panic: dedicated:132")));
+    }
+
+    #[test]
+    fn test_wheel_backend() {
+        let pool = TaskPool::new(&Config{ thread_count:2, timer_backend:TimerBackend::Wheel{ resolution:time::Duration::from_millis(1), threads:2 }, ..Config::default() });
+
+        // 大量任务共享时间轮
+        let count = Arc::new(AtomicU32::new(0));
+        let handles:Vec<TaskHandle> = (0..2000).map(|i| {
+            let c1 = count.clone();
+            pool.spawn(Arc::new(ClosuresAction::new("",1000 + i,3,time::Duration::from_millis(10 + i % 20),move |_| {
+                c1.fetch_add(1,Ordering::SeqCst);
+            }))).unwrap()
+        }).collect();
+        pool.rt_handle().block_on(async {
+            for h in &handles {
+                assert_eq!(h.wait().await,TaskStatus::Finished);
+            }
+        });
+        assert_eq!(count.load(Ordering::SeqCst),6000);
+
+        // 停止时取消时间轮上的等待并立即唤醒
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("",140,0,time::Duration::from_secs(60),|_| {}))).unwrap();
+        wait_until(|| handle.next_run().is_some());
+        handle.cancel();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Cancelled);
+
+        // 日期任务同样使用时间轮
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("Day 00:00:00",141,0,time::Duration::from_secs(0),|_| {}))).unwrap();
+        wait_until(|| handle.next_run().is_some());
+        handle.cancel();
+
+        // 手动时钟不使用时间轮，等待跟随时钟
+        let clock = ManualClock::new(Local.ymd(2026,3,2).and_hms(0,0,0));
+        let wheel = TimerBackend::Wheel{ resolution:time::Duration::from_millis(1), threads:1 };
+        let pool = TaskPool::new(&Config{ thread_count:1, timer_backend:wheel, clock:Arc::new(clock.clone()), ..Config::default() });
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("",142,1,time::Duration::from_secs(3600),|_| {}))).unwrap();
+        wait_until(|| clock.sleepers() == 1);
+        clock.advance(time::Duration::from_secs(3600));
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+    }
+
+    #[test]
+    fn test_wheel_driven() {
+        let wheel = TimerBackend::Wheel{ resolution:time::Duration::from_millis(1), threads:2 };
+
+        // 等待中的串行任务只在时间轮上登记，不占用tokio任务；tokio计时时每个任务都有一个任务循环
+        for (backend,alive) in [(TimerBackend::Tokio,1000),(wheel,0)] {
+            let pool = TaskPool::new(&Config{ thread_count:2, timer_backend:backend, ..Config::default() });
+            let handles = (0..1000).map(|i| {
+                pool.spawn(Arc::new(ClosuresAction::new("",2000 + i,0,time::Duration::from_secs(60),|_| {}))).unwrap()
+            }).collect::<Vec<TaskHandle>>();
+            wait_until(|| handles.iter().all(|v| v.next_run().is_some()));
+            wait_until(|| pool.rt_handle().metrics().num_alive_tasks() == alive);
+        }
+
+        // 暂停、恢复、重新设置计划和停止都会收回时间轮上的等待
+        let pool = TaskPool::new(&Config{ thread_count:2, timer_backend:wheel, ..Config::default() });
+        let count = Arc::new(AtomicU32::new(0));
+        let c1 = count.clone();
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("",3000,0,time::Duration::from_millis(20),move |_| {
+            c1.fetch_add(1,Ordering::SeqCst);
+        }))).unwrap();
+        wait_until(|| count.load(Ordering::SeqCst) >= 2);
+
+        handle.pause().unwrap();
+        wait_until(|| handle.status() == TaskStatus::Paused);
+        let paused = count.load(Ordering::SeqCst);
+        std::thread::sleep(time::Duration::from_millis(60));
+        assert_eq!(count.load(Ordering::SeqCst),paused);
+        assert_eq!(handle.next_run(),None);
+        wait_until(|| pool.rt_handle().metrics().num_alive_tasks() == 0);
+
+        handle.resume().unwrap();
+        wait_until(|| count.load(Ordering::SeqCst) >= paused + 2);
+
+        handle.reschedule(Schedule::Tick(time::Duration::from_secs(60))).unwrap();
+        wait_until(|| handle.next_run().is_some_and(|v| v > Local::now() + chrono::Duration::seconds(50)));
+        let rescheduled = count.load(Ordering::SeqCst);
+        std::thread::sleep(time::Duration::from_millis(60));
+        assert_eq!(count.load(Ordering::SeqCst),rescheduled);
+
+        handle.cancel();
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Cancelled);
+        assert!(matches!(pool.task_info(3000).unwrap_err().kind(),TErrorKind::TaskNotFound));
+    }
+
+    // 等待任务循环处理完，最多1秒
+    fn wait_until(cond:impl Fn() -> bool) {
+        let begin = time::Instant::now();
+        while !cond() {
+            assert!(begin.elapsed() < time::Duration::from_secs(1));
+            std::thread::sleep(time::Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(Local.ymd(2026,3,2).and_hms(4,59,0));
+        let pool = TaskPool::new(&Config{ thread_count:1, clock:Arc::new(clock.clone()), ..Config::default() });
+
+        // 日期任务只跟随时钟执行
+        let handle = pool.spawn(Arc::new(ClosuresAction::new("Day 05:00:00",150,2,time::Duration::from_secs(0),|_| {}))).unwrap();
+        wait_until(|| clock.sleepers() == 1);
+        assert_eq!(handle.next_run(),Some(Local.ymd(2026,3,2).and_hms(5,0,0)));
+        clock.advance(time::Duration::from_secs(59));
+        std::thread::sleep(time::Duration::from_millis(20));
+        assert_eq!(handle.run_count(),0);
+
+        clock.advance(time::Duration::from_secs(1));
+        wait_until(|| handle.run_count() == 1 && clock.sleepers() == 1);
+        assert_eq!(handle.info().last_run,Some(Local.ymd(2026,3,2).and_hms(5,0,0)));
+        assert_eq!(handle.next_run(),Some(Local.ymd(2026,3,3).and_hms(5,0,0)));
+
+        clock.set(Local.ymd(2026,3,3).and_hms(5,0,0));
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+        assert_eq!(handle.run_count(),2);
+
+        // 间隔任务和执行上下文使用同一个时钟
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let action = ClosuresAction::with_context(Schedule::Tick(time::Duration::from_secs(10)),151,3,TaskOptions::default(),move |ctx| -> Result<(),String> {
+            record.lock().unwrap().push((ctx.scheduled_at,ctx.started_at));
+            Ok(())
+        });
+        let handle = pool.spawn(Arc::new(action)).unwrap();
+        for _ in 0..3 {
+            wait_until(|| clock.sleepers() == 1);
+            clock.advance(time::Duration::from_secs(10));
         }
+        assert_eq!(pool.rt_handle().block_on(handle.wait()),TaskStatus::Finished);
+        let start = Local.ymd(2026,3,3).and_hms(5,0,0);
+        let expect:Vec<_> = (1..=3).map(|i| (start + chrono::Duration::seconds(i * 10),start + chrono::Duration::seconds(i * 10))).collect();
+        assert_eq!(*seen.lock().unwrap(),expect);
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod uuid {
-    use lazy_static::lazy_static;
-    use std::{ sync::Arc,thread };
     use std::sync::{atomic::AtomicI64,atomic::AtomicI16,atomic::Ordering  };
-    use chrono::{ Local,DateTime };
+    use chrono::Local;
 
     #[derive(Debug, Clone,Copy)]
     pub enum IDMode {
@@ -10,47 +9,67 @@ pub mod uuid {
         TimestampId,
     }
 
-    lazy_static! {
-        static ref LARGEID:Arc<AtomicI64> = Arc::new(AtomicI64::new(1));
+    // 每个调度器独立的ID生成器
+    pub struct IdGenerator {
+        large_id:AtomicI64,
+        stamp_id:AtomicI16,
     }
 
-    // 使用第一种，序列ID
-    pub fn set_seed(seed:i64) {
-        LARGEID.store(seed,Ordering::Relaxed);
-    }
+    impl IdGenerator {
+        pub fn new(seed:i64) -> IdGenerator {
+            IdGenerator {
+                large_id: AtomicI64::new(seed),
+                stamp_id: AtomicI16::new(1),
+            }
+        }
 
-    // 获得下一个ID
-    pub fn next_big_id() -> i64 {
-        let rvalue = LARGEID.load(Ordering::Relaxed);
-        LARGEID.fetch_add(1,Ordering::SeqCst);
-        rvalue
-    }
+        // 使用第一种，序列ID
+        pub fn set_seed(&self,seed:i64) {
+            self.large_id.store(seed,Ordering::SeqCst);
+        }
 
-    // 使用第二种，时间戳Id
-    lazy_static! {
-        static ref TIMESTAPID:Arc<AtomicI16> = Arc::new(AtomicI16::new(1));
-    }
+        // 获得下一个ID
+        pub fn next_big_id(&self) -> i64 {
+            self.large_id.fetch_add(1,Ordering::SeqCst)
+        }
 
-    pub fn next_timestamp_id() -> i64 {
-        TIMESTAPID.fetch_add(1,Ordering::SeqCst);
-        let mut ids = TIMESTAPID.load(Ordering::Relaxed);
-        if ids >= 99 {
-            TIMESTAPID.store(1,Ordering::Relaxed);
-            ids = 1
+        // 序列跳过已经使用的ID，恢复的任务保留原来的ID
+        pub fn reserve(&self,id:i64) {
+            self.large_id.fetch_max(id.saturating_add(1),Ordering::SeqCst);
         }
 
-        (Local::now().timestamp() * 100) + (ids as i64)
+        // 使用第二种，时间戳Id
+        pub fn next_timestamp_id(&self) -> i64 {
+            let mut ids = self.stamp_id.fetch_add(1,Ordering::SeqCst) + 1;
+            if ids >= 99 {
+                self.stamp_id.store(1,Ordering::Relaxed);
+                ids = 1
+            }
+
+            (Local::now().timestamp() * 100) + (ids as i64)
+        }
+
+        pub fn next_id(&self,mode:IDMode) -> u64 {
+            match mode {
+                IDMode::SequenceId => { self.next_big_id() as u64 }
+                IDMode::TimestampId => { self.next_timestamp_id() as u64 }
+            }
+        }
     }
 
     // 测试用例
     #[test]
     fn test_set_seed() {
-        set_seed(100);
+        use std::{sync::Arc,thread};
+
+        let ids = Arc::new(IdGenerator::new(1));
+        ids.set_seed(100);
         let mut hv = vec![];
-        for i in 1..10 {
-            let h = thread::spawn(|| {
-                for i in 1..5 {
-                    let next = next_big_id();
+        for _ in 1..10 {
+            let gen = ids.clone();
+            let h = thread::spawn(move || {
+                for _ in 1..5 {
+                    let next = gen.next_big_id();
                     println!("{}",next);
                 }
             });
@@ -58,17 +77,22 @@ pub mod uuid {
         }
 
         for tv in hv {
-            tv.join();
+            tv.join().unwrap();
         }
+        assert_eq!(ids.next_big_id(),136);
     }
 
     #[test]
     fn test_next_timestamp() {
+        use std::{sync::Arc,thread};
+
+        let ids = Arc::new(IdGenerator::new(1));
         let mut hv = vec![];
-        for i in 1..10 {
-            let h = thread::spawn(|| {
-                for i in 1..5 {
-                    let next = next_timestamp_id();
+        for _ in 1..10 {
+            let gen = ids.clone();
+            let h = thread::spawn(move || {
+                for _ in 1..5 {
+                    let next = gen.next_timestamp_id();
                     println!("{}",next);
                 }
             });
@@ -76,7 +100,7 @@ pub mod uuid {
         }
 
         for tv in hv {
-            tv.join();
+            tv.join().unwrap();
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,681 @@
+#[allow(clippy::module_inception)]
+pub mod wheel {
+    use std::sync::{Arc,Mutex,OnceLock};
+    use std::sync::atomic::{AtomicBool,AtomicUsize,Ordering};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context,Poll,Waker};
+    use std::thread::{self,Thread};
+    use std::time::{Duration,Instant};
+    use crate::scheduler::scheduler::TimerBackend;
+    use crate::clock::clock::{Clock, SystemClock};
+    use crate::hooks::hooks::{Busy, Idle};
+    #[cfg(any(test, feature = "testing"))]
+    use crate::hooks::hooks::Hooks;
+
+    // 每一层64个槽，6层可以覆盖2^36个刻度
+    const SLOT_BITS:u32 = 6;
+    const SLOTS:usize = 1 << SLOT_BITS;
+    const LEVELS:usize = 6;
+
+    struct Entry {
+        deadline:u64,
+        waker:Waker,
+        place:(usize,usize), // 所在的槽以及在槽中的位置
+    }
+
+    // 槽中的位置可以复用，generation区分同一个位置上前后不同的等待
+    struct Node {
+        generation:u64,
+        entry:Option<Entry>,
+    }
+
+    // 分层时间轮，按刻度计时，插入和取消都是O(1)
+    struct Levels {
+        elapsed:u64, // 已经处理到的刻度
+        wake_at:u64, // 时间轮线程下一次醒来的刻度，没有等待时为u64::MAX
+        slots:Vec<Vec<usize>>,
+        occupied:[u64;LEVELS], // 每一层哪些槽中有等待，查找下一个刻度时不用逐个检查槽
+        nodes:Vec<Node>,
+        free:Vec<usize>,
+        len:usize,
+    }
+
+    impl Levels {
+        fn new() -> Levels {
+            Levels {
+                elapsed:0,
+                wake_at:u64::MAX,
+                slots:(0..LEVELS * SLOTS).map(|_| Vec::new()).collect(),
+                occupied:[0;LEVELS],
+                nodes:Vec::new(),
+                free:Vec::new(),
+                len:0,
+            }
+        }
+
+        // 到期刻度所在的层和槽，超出范围的放在最高层，到时再重新放置
+        fn slot_for(&self,deadline:u64) -> usize {
+            let masked = (deadline ^ self.elapsed) | (SLOTS as u64 - 1);
+            let significant = 63 - masked.leading_zeros();
+            let level = ((significant / SLOT_BITS) as usize).min(LEVELS - 1);
+            let index = (deadline >> (level as u32 * SLOT_BITS)) as usize & (SLOTS - 1);
+            level * SLOTS + index
+        }
+
+        fn place(&mut self,idx:usize) {
+            let deadline = match &self.nodes[idx].entry {
+                Some(v) => { v.deadline }
+                None => { return; }
+            };
+            let slot = self.slot_for(deadline);
+            self.slots[slot].push(idx);
+            self.occupied[slot / SLOTS] |= 1 << (slot % SLOTS);
+            let pos = self.slots[slot].len() - 1;
+            if let Some(v) = self.nodes[idx].entry.as_mut() {
+                v.place = (slot,pos);
+            }
+        }
+
+        // 已经到期时返回None
+        fn insert(&mut self,deadline:u64,waker:Waker) -> Option<(usize,u64)> {
+            if deadline <= self.elapsed {
+                return None;
+            }
+            let idx = match self.free.pop() {
+                Some(v) => { v }
+                None => {
+                    self.nodes.push(Node{ generation:0, entry:None });
+                    self.nodes.len() - 1
+                }
+            };
+            let node = &mut self.nodes[idx];
+            node.generation += 1;
+            node.entry = Some(Entry{ deadline, waker, place:(0,0) });
+            let key = (idx,node.generation);
+            self.place(idx);
+            self.len += 1;
+            Some(key)
+        }
+
+        // 从槽中移除，最后一个元素移到空出的位置
+        fn unlink(&mut self,idx:usize) -> Option<Entry> {
+            let entry = self.nodes[idx].entry.take()?;
+            let (slot,pos) = entry.place;
+            self.slots[slot].swap_remove(pos);
+            if let Some(&moved) = self.slots[slot].get(pos) {
+                if let Some(v) = self.nodes[moved].entry.as_mut() {
+                    v.place = (slot,pos);
+                }
+            }
+            if self.slots[slot].is_empty() {
+                self.occupied[slot / SLOTS] &= !(1 << (slot % SLOTS));
+            }
+            self.free.push(idx);
+            self.len -= 1;
+            Some(entry)
+        }
+
+        fn is_pending(&self,key:(usize,u64)) -> bool {
+            let node = &self.nodes[key.0];
+            node.generation == key.1 && node.entry.is_some()
+        }
+
+        fn update_waker(&mut self,key:(usize,u64),waker:&Waker) {
+            let node = &mut self.nodes[key.0];
+            if node.generation != key.1 {
+                return;
+            }
+            if let Some(v) = node.entry.as_mut() {
+                if !v.waker.will_wake(waker) {
+                    v.waker = waker.clone();
+                }
+            }
+        }
+
+        // 还没有到期时移除，返回是否移除了
+        fn cancel(&mut self,key:(usize,u64)) -> bool {
+            self.is_pending(key) && self.unlink(key.0).is_some()
+        }
+
+        // 取出槽中所有的等待
+        fn take_slot(&mut self,slot:usize) -> Vec<usize> {
+            self.occupied[slot / SLOTS] &= !(1 << (slot % SLOTS));
+            std::mem::take(&mut self.slots[slot])
+        }
+
+        // 下一个需要处理的刻度：最低层是到期的刻度，上层是下放到下层的刻度
+        fn next_expiration(&self) -> Option<u64> {
+            if self.len == 0 {
+                return None;
+            }
+            let mut next:Option<u64> = None;
+            for (level,&bits) in self.occupied.iter().enumerate() {
+                if bits == 0 {
+                    continue;
+                }
+                let shift = level as u32 * SLOT_BITS;
+                let slot_range = 1u64 << shift;
+                let level_range = slot_range << SLOT_BITS;
+                let base = self.elapsed & !(level_range - 1);
+                // 当前位置之后第一个有等待的槽，没有时绕回这一层的开头，最高层的槽可能已经转过一圈
+                let current = (self.elapsed >> shift) & (SLOTS as u64 - 1);
+                let after = bits & ((!1u64) << current);
+                let at = if after != 0 {
+                    base + after.trailing_zeros() as u64 * slot_range
+                }else {
+                    base + bits.trailing_zeros() as u64 * slot_range + level_range
+                };
+                next = Some(next.map_or(at,|v| v.min(at)));
+            }
+            next
+        }
+
+        // 推进到目标刻度，跳过没有等待的刻度，返回到期的等待
+        fn advance(&mut self,target:u64,fired:&mut Vec<Waker>) {
+            loop {
+                let now = match self.next_expiration() {
+                    Some(v) if v <= target => { v }
+                    _ => {
+                        self.elapsed = self.elapsed.max(target);
+                        return;
+                    }
+                };
+                self.elapsed = now;
+                // 从高到低把到达的槽重新放置到下层
+                for level in (1..LEVELS).rev() {
+                    let shift = level as u32 * SLOT_BITS;
+                    if now & ((1u64 << shift) - 1) != 0 {
+                        continue;
+                    }
+                    let slot = level * SLOTS + ((now >> shift) as usize & (SLOTS - 1));
+                    for idx in self.take_slot(slot) {
+                        let due = match &self.nodes[idx].entry {
+                            Some(v) => { v.deadline <= now }
+                            None => { continue; }
+                        };
+                        if due {
+                            // 已经不在槽中，直接释放
+                            if let Some(v) = self.nodes[idx].entry.take() {
+                                self.free.push(idx);
+                                self.len -= 1;
+                                fired.push(v.waker);
+                            }
+                        }else {
+                            self.place(idx);
+                        }
+                    }
+                }
+                let slot = now as usize & (SLOTS - 1);
+                for idx in self.take_slot(slot) {
+                    if let Some(v) = self.nodes[idx].entry.take() {
+                        self.free.push(idx);
+                        self.len -= 1;
+                        fired.push(v.waker);
+                    }
+                }
+            }
+        }
+    }
+
+    // 一个时间轮线程和它的计时
+    struct Shard {
+        levels:Mutex<Levels>,
+        clock:Arc<dyn Clock>,
+        origin:Instant,
+        resolution:Duration,
+        thread:OnceLock<Thread>,
+        stopped:AtomicBool,
+    }
+
+    impl Shard {
+        // 向上取整，不会提前唤醒
+        fn tick_ceil(&self,at:Instant) -> u64 {
+            let nanos = at.saturating_duration_since(self.origin).as_nanos();
+            nanos.div_ceil(self.resolution.as_nanos()) as u64
+        }
+
+        fn tick_floor(&self,at:Instant) -> u64 {
+            (at.saturating_duration_since(self.origin).as_nanos() / self.resolution.as_nanos()) as u64
+        }
+
+        fn instant_of(&self,tick:u64) -> Instant {
+            self.origin + Duration::from_nanos((tick as u128 * self.resolution.as_nanos()) as u64)
+        }
+
+        fn unpark(&self) {
+            if let Some(t) = self.thread.get() {
+                t.unpark();
+            }
+        }
+
+        // 到期时唤醒waker，已经到期时返回None
+        fn insert(&self,deadline:Instant,waker:Waker) -> Option<(usize,u64)> {
+            let mut levels = self.levels.lock().ok()?;
+            if levels.len == 0 {
+                // 空闲时线程没有推进刻度，从当前时间开始
+                let now = self.tick_floor(self.clock.instant());
+                levels.elapsed = levels.elapsed.max(now);
+            }
+            let tick = self.tick_ceil(deadline);
+            let key = levels.insert(tick,waker)?;
+            // 早于线程醒来的时间，唤醒线程重新计算
+            let early = tick < levels.wake_at;
+            if early {
+                levels.wake_at = tick;
+            }
+            drop(levels);
+            if early {
+                self.unpark();
+            }
+            Some(key)
+        }
+
+        fn cancel(&self,key:(usize,u64)) -> bool {
+            match self.levels.lock() {
+                Ok(mut levels) => { levels.cancel(key) }
+                Err(_) => { false }
+            }
+        }
+
+        fn run(&self) {
+            let mut fired = Vec::new();
+            while !self.stopped.load(Ordering::SeqCst) {
+                let next = match self.levels.lock() {
+                    Ok(mut levels) => {
+                        levels.advance(self.tick_floor(self.clock.instant()),&mut fired);
+                        // 在锁内记录醒来的刻度，之后插入更早的等待会唤醒线程
+                        let next = levels.next_expiration();
+                        levels.wake_at = next.unwrap_or(u64::MAX);
+                        next
+                    }
+                    Err(_) => { break; }
+                };
+                // 在锁外唤醒
+                for waker in fired.drain(..) {
+                    waker.wake();
+                }
+                // 休眠到最早有等待的槽
+                match next {
+                    None => { thread::park(); }
+                    Some(tick) => {
+                        thread::park_timeout(self.instant_of(tick).saturating_duration_since(self.clock.instant()));
+                    }
+                }
+            }
+        }
+    }
+
+    // 时间轮上登记的一个等待
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) struct WheelKey {
+        shard:usize,
+        key:(usize,u64),
+    }
+
+    // 时间轮计时器，所有的等待分散在几个线程中，按刻度批量唤醒
+    pub(crate) struct TimingWheel {
+        shards:Vec<Arc<Shard>>,
+        next:AtomicUsize,
+    }
+
+    impl TimingWheel {
+        pub fn new(resolution:Duration,threads:usize,clock:Arc<dyn Clock>) -> TimingWheel {
+            let origin = clock.instant();
+            let resolution = resolution.max(Duration::from_millis(1));
+            let shards = (0..threads.max(1)).map(|i| {
+                let shard = Arc::new(Shard {
+                    levels:Mutex::new(Levels::new()),
+                    clock:clock.clone(),
+                    origin,
+                    resolution,
+                    thread:OnceLock::new(),
+                    stopped:AtomicBool::new(false),
+                });
+                let driver = shard.clone();
+                let handle = thread::Builder::new()
+                    .name(format!("grape-timer-wheel-{}",i))
+                    .spawn(move || driver.run())
+                    .unwrap();
+                let _ = shard.thread.set(handle.thread().clone());
+                shard
+            }).collect();
+            TimingWheel { shards, next:AtomicUsize::new(0) }
+        }
+
+        fn pick(&self) -> usize {
+            self.next.fetch_add(1,Ordering::Relaxed) % self.shards.len()
+        }
+
+        pub fn sleep(&self,dur:Duration) -> WheelSleep {
+            let shard = self.shards[self.pick()].clone();
+            let deadline = shard.clock.instant() + dur;
+            WheelSleep { shard, deadline, key:None, done:false }
+        }
+
+        // 不经过future直接登记，到期时在时间轮线程中唤醒waker，已经到期时返回None
+        pub fn schedule(&self,deadline:Instant,waker:Waker) -> Option<WheelKey> {
+            let shard = self.pick();
+            let key = self.shards[shard].insert(deadline,waker)?;
+            Some(WheelKey{ shard, key })
+        }
+
+        // 取消还没有到期的等待，已经到期或已经取消时返回false
+        pub fn cancel(&self,key:WheelKey) -> bool {
+            self.shards[key.shard].cancel(key.key)
+        }
+    }
+
+    impl Drop for TimingWheel {
+        fn drop(&mut self) {
+            for shard in &self.shards {
+                shard.stopped.store(true,Ordering::SeqCst);
+                shard.unpark();
+            }
+        }
+    }
+
+    /// future of a wait on the timing wheel [时间轮上的一次等待]
+    pub(crate) struct WheelSleep {
+        shard:Arc<Shard>,
+        deadline:Instant,
+        key:Option<(usize,u64)>,
+        done:bool,
+    }
+
+    impl Future for WheelSleep {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            let this = self.get_mut();
+            if this.done {
+                return Poll::Ready(());
+            }
+            let key = match this.key {
+                Some(v) => { v }
+                None => {
+                    match this.shard.insert(this.deadline,cx.waker().clone()) {
+                        Some(key) => {
+                            this.key = Some(key);
+                            return Poll::Pending;
+                        }
+                        None => {
+                            this.done = true;
+                            return Poll::Ready(());
+                        }
+                    }
+                }
+            };
+            let mut levels = match this.shard.levels.lock() {
+                Ok(v) => { v }
+                Err(_) => { return Poll::Ready(()); }
+            };
+            if levels.is_pending(key) {
+                levels.update_waker(key,cx.waker());
+                Poll::Pending
+            }else {
+                this.key = None;
+                this.done = true;
+                Poll::Ready(())
+            }
+        }
+    }
+
+    impl Drop for WheelSleep {
+        fn drop(&mut self) {
+            if let Some(key) = self.key.take() {
+                self.shard.cancel(key);
+            }
+        }
+    }
+
+    // 任务使用的时钟和计时器，时间轮从时钟读取时间
+    // hooks为测试调度器观察任务的活动和执行
+    #[derive(Clone)]
+    pub(crate) struct Timer {
+        clock:Arc<dyn Clock>,
+        wheel:Option<Arc<TimingWheel>>,
+        #[cfg(any(test, feature = "testing"))]
+        hooks:Option<Hooks>,
+    }
+
+    impl Default for Timer {
+        fn default() -> Self {
+            Timer::new(TimerBackend::Tokio,Arc::new(SystemClock))
+        }
+    }
+
+    impl Timer {
+        pub fn new(backend:TimerBackend,clock:Arc<dyn Clock>) -> Timer {
+            let wheel = match backend {
+                TimerBackend::Tokio => { None }
+                // 时间轮线程按真实时间休眠，手动推进的时钟使用它自己的等待
+                TimerBackend::Wheel{ .. } if !clock.is_realtime() => { None }
+                TimerBackend::Wheel{ resolution, threads } => { Some(Arc::new(TimingWheel::new(resolution,threads,clock.clone()))) }
+            };
+            Timer {
+                clock,
+                wheel,
+                #[cfg(any(test, feature = "testing"))]
+                hooks:None,
+            }
+        }
+
+        // 换成新的计时器后端和时钟，保留测试调度器的观察者
+        pub fn renew(&self,backend:TimerBackend,clock:Arc<dyn Clock>) -> Timer {
+            #[allow(unused_mut)]
+            let mut timer = Timer::new(backend,clock);
+            #[cfg(any(test, feature = "testing"))]
+            {
+                timer.hooks = self.hooks.clone();
+            }
+            timer
+        }
+
+        #[cfg(any(test, feature = "testing"))]
+        pub fn with_hooks(mut self,hooks:Option<Hooks>) -> Timer {
+            self.hooks = hooks;
+            self
+        }
+
+        #[cfg(any(test, feature = "testing"))]
+        pub fn hooks(&self) -> Option<&Hooks> {
+            self.hooks.as_ref()
+        }
+
+        pub fn busy(&self) -> Busy {
+            Busy::new(self)
+        }
+
+        pub fn idle(&self) -> Idle {
+            Idle::new(self)
+        }
+
+        pub fn clock(&self) -> &Arc<dyn Clock> {
+            &self.clock
+        }
+
+        // 使用时间轮计时时的时间轮
+        pub fn wheel(&self) -> Option<&Arc<TimingWheel>> {
+            self.wheel.as_ref()
+        }
+
+        pub async fn sleep(&self,dur:Duration) {
+            // 不需要等待时不进入空闲，测试调度器不会在执行之前认为任务已经在等待
+            if dur.is_zero() {
+                return;
+            }
+            match &self.wheel {
+                Some(w) => {
+                    let _idle = self.idle();
+                    w.sleep(dur).await
+                }
+                None => {
+                    // 先结束等待再离开时钟，测试调度器不会看到两者都为空的间隙
+                    let mut sleep = self.clock.sleep(dur);
+                    let _idle = self.idle();
+                    (&mut sleep).await
+                }
+            }
+        }
+    }
+
+    // 内部函数测试
+    #[cfg(test)]
+    struct NoopWake;
+
+    #[cfg(test)]
+    impl std::task::Wake for NoopWake {
+        fn wake(self:Arc<Self>) {}
+    }
+
+    #[test]
+    fn test_levels_skip_empty_ticks() {
+        let noop = Waker::from(Arc::new(NoopWake));
+        let mut levels = Levels::new();
+        let mut fired = Vec::new();
+
+        // 各层的等待，下一个需要处理的刻度是到期或下放的刻度
+        for deadline in [5u64,70,4100,300000] {
+            levels.insert(deadline,noop.clone());
+        }
+        assert_eq!(levels.next_expiration(),Some(5));
+        levels.advance(4,&mut fired);
+        assert!(fired.is_empty());
+        assert_eq!(levels.elapsed,4);
+
+        levels.advance(5,&mut fired);
+        assert_eq!(fired.len(),1);
+        // 70在第二层，到64时下放
+        assert_eq!(levels.next_expiration(),Some(64));
+        levels.advance(64,&mut fired);
+        assert_eq!(fired.len(),1);
+        assert_eq!(levels.next_expiration(),Some(70));
+
+        // 一次跳过大量空的刻度
+        fired.clear();
+        levels.advance(299999,&mut fired);
+        assert_eq!(fired.len(),2);
+        assert_eq!(levels.len,1);
+        assert_eq!(levels.next_expiration(),Some(300000));
+        levels.advance(1 << 40,&mut fired);
+        assert_eq!(fired.len(),3);
+        assert_eq!(levels.next_expiration(),None);
+        assert_eq!(levels.elapsed,1 << 40);
+
+        // 超出范围的等待放在最高层，转过一圈后仍按时到期
+        let far = levels.elapsed + (1 << 37) + 3;
+        levels.insert(far,noop.clone());
+        levels.advance(far - 1,&mut fired);
+        assert_eq!(fired.len(),3);
+        levels.advance(far,&mut fired);
+        assert_eq!(fired.len(),4);
+    }
+
+    #[cfg(test)]
+    struct RecordWake(u64,Arc<Mutex<Vec<u64>>>);
+
+    #[cfg(test)]
+    impl std::task::Wake for RecordWake {
+        fn wake(self:Arc<Self>) {
+            self.1.lock().unwrap().push(self.0);
+        }
+    }
+
+    #[test]
+    fn test_levels_random() {
+        use rand::{Rng,SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let woken = Arc::new(Mutex::new(Vec::new()));
+        let mut levels = Levels::new();
+        let mut pending:Vec<((usize,u64),u64)> = Vec::new();
+        let mut fired = Vec::new();
+
+        // 和逐个检查等待的结果比较：下一个刻度不晚于最早的到期，推进时恰好唤醒到期的等待
+        for _ in 0..20000 {
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    let span = [64u64,4096,1 << 20,1 << 38][rng.gen_range(0..4)];
+                    let deadline = levels.elapsed + 1 + rng.gen_range(0..span);
+                    let key = levels.insert(deadline,Waker::from(Arc::new(RecordWake(deadline,woken.clone())))).unwrap();
+                    pending.push((key,deadline));
+                }
+                2 if !pending.is_empty() => {
+                    let (key,_) = pending.swap_remove(rng.gen_range(0..pending.len()));
+                    assert!(levels.cancel(key));
+                }
+                _ => {
+                    let bits = rng.gen_range(0..24);
+                    let target = levels.elapsed + rng.gen_range(0..1u64 << bits);
+                    levels.advance(target,&mut fired);
+                    fired.drain(..).for_each(Waker::wake);
+                    let mut expect = pending.iter().filter(|v| v.1 <= target).map(|v| v.1).collect::<Vec<u64>>();
+                    pending.retain(|v| v.1 > target);
+                    let mut got = std::mem::take(&mut *woken.lock().unwrap());
+                    expect.sort_unstable();
+                    got.sort_unstable();
+                    assert_eq!(got,expect);
+                }
+            }
+            let earliest = pending.iter().map(|v| v.1).min();
+            let next = levels.next_expiration();
+            assert_eq!(next.is_some(),earliest.is_some());
+            if let (Some(next),Some(earliest)) = (next,earliest) {
+                assert!(next > levels.elapsed && next <= earliest,"{} {} {}",levels.elapsed,next,earliest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wheel::wheel::TimingWheel;
+    use crate::clock::clock::SystemClock;
+    use std::sync::Arc;
+    use std::time::{Duration,Instant};
+
+    #[test]
+    fn test_wheel_sleep() {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let wheel = TimingWheel::new(Duration::from_millis(1),2,Arc::new(SystemClock));
+
+        // 跨层的等待都不会提前唤醒
+        for ms in [1,20,70,300,1100] {
+            let begin = Instant::now();
+            rt.block_on(wheel.sleep(Duration::from_millis(ms)));
+            let elapsed = begin.elapsed();
+            assert!(elapsed >= Duration::from_millis(ms),"{}ms woke after {:?}",ms,elapsed);
+            assert!(elapsed < Duration::from_millis(ms + 1000),"{}ms woke after {:?}",ms,elapsed);
+        }
+
+        // 同时等待的按到期顺序唤醒
+        let order = rt.block_on(async {
+            let (tx,mut rx) = tokio::sync::mpsc::unbounded_channel();
+            for ms in [90u64,30,60,10] {
+                let tx = tx.clone();
+                let sleep = wheel.sleep(Duration::from_millis(ms));
+                tokio::spawn(async move {
+                    sleep.await;
+                    let _ = tx.send(ms);
+                });
+            }
+            drop(tx);
+            let mut order = Vec::new();
+            while let Some(v) = rx.recv().await {
+                order.push(v);
+            }
+            order
+        });
+        assert_eq!(order,vec![10,30,60,90]);
+
+        // 被丢弃的等待从时间轮中移除
+        rt.block_on(async {
+            let long = wheel.sleep(Duration::from_millis(500));
+            tokio::select! {
+                _ = long => { panic!("cancelled sleep woke"); }
+                _ = wheel.sleep(Duration::from_millis(10)) => {}
+            }
+            wheel.sleep(Duration::from_millis(5)).await;
+        });
+    }
+}